@@ -11,7 +11,24 @@ static USERNAME_RE: LazyLock<Regex> = LazyLock::new(|| {
 });
 
 static PASSWORD_RE: LazyLock<Regex> = LazyLock::new(|| {
-    Regex::new(r"(?i)(\b(?:password|secret)\s+)(?:(\d+)\s+)?(\S+)").expect("valid password regex")
+    Regex::new(
+        r"(?i)(\b(?:password|passwd|secret)\s+)(?:(\d+|cipher|irreversible-cipher|simple)\s+)?(\S+)",
+    )
+    .expect("valid password regex")
+});
+
+/// IKE pre-shared keys, including the ASA `ikev1`/`ikev2 ... key` forms.
+static PRESHARED_KEY_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)(\b(?:pre-shared-key|ikev[12]\s+(?:[\w-]+\s+)*?key)\s+)(?:(\d)\s+)?(\S+)")
+        .expect("valid pre-shared key regex")
+});
+
+/// VRP/Comware encrypted values outside a `password` statement, such as SNMP
+/// communities and keys. Only the encoded forms count, so cipher-suite
+/// settings like `ssh cipher encryption high` are left alone.
+static CIPHER_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)(\b(?:cipher|irreversible-cipher)\s+)((?:%\^%#|%@%@|\$1[a-z]\$)\S+)")
+        .expect("valid cipher regex")
 });
 
 static KEYSTRING_RE: LazyLock<Regex> = LazyLock::new(|| {
@@ -121,6 +138,40 @@ impl Anonymizer {
             })
             .into_owned();
 
+        line = PRESHARED_KEY_RE
+            .replace_all(&line, |caps: &Captures| {
+                let algo_part = caps
+                    .get(2)
+                    .map(|m| format!("{} ", m.as_str()))
+                    .unwrap_or_default();
+                let original = caps[3].to_string();
+                let replacement = self.map_password(&original);
+                if let Some(sink) = sink.as_deref_mut() {
+                    sink.push(TokenCapture::new(
+                        TokenKind::Secret,
+                        original,
+                        Some(replacement.clone()),
+                    ));
+                }
+                format!("{}{algo_part}{replacement}", &caps[1])
+            })
+            .into_owned();
+
+        line = CIPHER_RE
+            .replace_all(&line, |caps: &Captures| {
+                let original = caps[2].to_string();
+                let replacement = self.map_password(&original);
+                if let Some(sink) = sink.as_deref_mut() {
+                    sink.push(TokenCapture::new(
+                        TokenKind::Secret,
+                        original,
+                        Some(replacement.clone()),
+                    ));
+                }
+                format!("{}{}", &caps[1], replacement)
+            })
+            .into_owned();
+
         line = KEYSTRING_RE
             .replace_all(&line, |caps: &Captures| {
                 let original = caps[2].to_string();
@@ -279,10 +330,34 @@ pub fn collect_plain_tokens(input: &str) -> Vec<TokenCapture> {
         ));
     }
 
+    let mut secret_spans = Vec::new();
     for caps in PASSWORD_RE.captures_iter(input) {
+        let value = caps.get(3).unwrap();
+        secret_spans.push(value.range());
+        tokens.push(TokenCapture::new(
+            TokenKind::Secret,
+            value.as_str().to_string(),
+            None,
+        ));
+    }
+
+    for caps in PRESHARED_KEY_RE.captures_iter(input) {
+        tokens.push(TokenCapture::new(
+            TokenKind::Secret,
+            caps[3].to_string(),
+            None,
+        ));
+    }
+
+    // `password cipher <value>` is already covered by PASSWORD_RE.
+    for caps in CIPHER_RE.captures_iter(input) {
+        let value = caps.get(2).unwrap();
+        if secret_spans.contains(&value.range()) {
+            continue;
+        }
         tokens.push(TokenCapture::new(
             TokenKind::Secret,
-            caps.get(3).unwrap().as_str().to_string(),
+            value.as_str().to_string(),
             None,
         ));
     }
@@ -377,4 +452,54 @@ mod tests {
                 .any(|t| matches!(t.kind, TokenKind::Asn) && t.original == "65001")
         );
     }
+
+    #[test]
+    fn anonymizer_scrubs_vrp_cipher_forms() {
+        let mut anon = Anonymizer::new();
+        let local_user = anon.scrub("local-user admin password irreversible-cipher $1c$abc$xyz$");
+        assert!(local_user.contains("password irreversible-cipher scrambled"));
+        assert!(!local_user.contains("$1c$"));
+
+        let community = anon.scrub("snmp-agent community read cipher %^%#Zm9vYmFy%^%#");
+        assert!(community.ends_with("cipher scrambled2"));
+
+        let suite = anon.scrub("ssh cipher encryption high");
+        assert_eq!(suite, "ssh cipher encryption high");
+    }
+
+    #[test]
+    fn collect_plain_tokens_does_not_duplicate_cipher_secrets() {
+        let tokens = collect_plain_tokens("local-user admin password cipher %^%#abc%^%#");
+        let secrets = tokens
+            .iter()
+            .filter(|t| matches!(t.kind, TokenKind::Secret))
+            .map(|t| t.original.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(secrets, vec!["%^%#abc%^%#"]);
+    }
+
+    #[test]
+    fn anonymizer_scrubs_asa_secrets() {
+        let mut anon = Anonymizer::new();
+        assert_eq!(
+            anon.scrub("passwd 2KFQnbNIdI.2KYOU encrypted"),
+            "passwd scrambled1 encrypted"
+        );
+        assert_eq!(
+            anon.scrub("enable password 8Ry2YjIyt7RRXU24 encrypted"),
+            "enable password scrambled2 encrypted"
+        );
+        assert_eq!(
+            anon.scrub(" ikev1 pre-shared-key Lab-Psk-1"),
+            " ikev1 pre-shared-key scrambled3"
+        );
+        assert_eq!(
+            anon.scrub(" ikev2 remote-authentication pre-shared-key Lab-Psk-1"),
+            " ikev2 remote-authentication pre-shared-key scrambled3"
+        );
+        assert_eq!(
+            anon.scrub(" ikev2 local-authentication key 0 Lab-Psk-2"),
+            " ikev2 local-authentication key 0 scrambled4"
+        );
+    }
 }
@@ -0,0 +1,199 @@
+use std::io::{self, Cursor, Read};
+
+use flate2::read::MultiGzDecoder;
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+/// The `ustar` magic at this offset of the first header marks a tar archive,
+/// whether POSIX or GNU.
+const TAR_MAGIC_OFFSET: usize = 257;
+const TAR_MAGIC: &[u8] = b"ustar";
+/// Enough of a stream to recognise any of the formats above.
+const HEADER_LEN: usize = 512;
+/// The most a compressed input may decompress to, so a small file cannot
+/// exhaust memory.
+const MAX_UNPACKED_LEN: u64 = 1 << 30;
+
+/// A regular file inside a tar archive.
+pub(crate) struct Member {
+    /// The path of the member inside the archive.
+    pub name: String,
+    pub bytes: Vec<u8>,
+}
+
+/// Expand a tar archive, compressed or not, into the regular files `allows`
+/// accepts, sorted by path and each decompressed in turn. Formats are
+/// recognised by their magic bytes rather than the file name. Returns `None`
+/// when `reader` does not hold a tar archive, having read only its first
+/// block.
+pub(crate) fn members(
+    reader: impl Read,
+    allows: impl Fn(&str) -> bool,
+) -> io::Result<Option<Vec<Member>>> {
+    let mut stream = decoder(reader)?;
+    let header = read_header(&mut stream)?;
+    if !is_tar(&header) {
+        return Ok(None);
+    }
+
+    let mut archive = tar::Archive::new(Cursor::new(header).chain(stream));
+    let mut members = Vec::new();
+    for entry in archive.entries()? {
+        let entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let name = entry.path()?.to_string_lossy().into_owned();
+        if !allows(&name) {
+            continue;
+        }
+        members.push(Member {
+            name,
+            bytes: decompress(entry)?,
+        });
+    }
+    members.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(Some(members))
+}
+
+/// Read `reader` to the end, decompressing gzip and zstd streams.
+pub(crate) fn decompress(reader: impl Read) -> io::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    decoder(reader)?.read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Wrap `reader` in the decoder its magic bytes call for. Decompressed
+/// output is limited to [`MAX_UNPACKED_LEN`] bytes.
+fn decoder<'a>(mut reader: impl Read + 'a) -> io::Result<Box<dyn Read + 'a>> {
+    let header = read_header(&mut reader)?;
+    let gzip = header.starts_with(GZIP_MAGIC);
+    let zstd = header.starts_with(ZSTD_MAGIC);
+    let raw = Cursor::new(header).chain(reader);
+    Ok(if gzip {
+        Box::new(Limited::new(MultiGzDecoder::new(raw)))
+    } else if zstd {
+        Box::new(Limited::new(zstd::stream::read::Decoder::new(raw)?))
+    } else {
+        Box::new(raw)
+    })
+}
+
+/// Read up to [`HEADER_LEN`] bytes, fewer only at the end of the stream.
+fn read_header(reader: &mut impl Read) -> io::Result<Vec<u8>> {
+    let mut header = Vec::with_capacity(HEADER_LEN);
+    reader.take(HEADER_LEN as u64).read_to_end(&mut header)?;
+    Ok(header)
+}
+
+fn is_tar(bytes: &[u8]) -> bool {
+    bytes
+        .get(TAR_MAGIC_OFFSET..TAR_MAGIC_OFFSET + TAR_MAGIC.len())
+        .is_some_and(|magic| magic == TAR_MAGIC)
+}
+
+/// A reader that fails, rather than stopping short like [`Read::take`], once
+/// more than `limit` bytes have been read.
+struct Limited<R> {
+    inner: R,
+    limit: u64,
+    remaining: u64,
+}
+
+impl<R> Limited<R> {
+    const fn new(inner: R) -> Self {
+        Self::with_limit(inner, MAX_UNPACKED_LEN)
+    }
+
+    const fn with_limit(inner: R, limit: u64) -> Self {
+        Self {
+            inner,
+            limit,
+            remaining: limit,
+        }
+    }
+}
+
+impl<R: Read> Read for Limited<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.remaining = self.remaining.checked_sub(read as u64).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("input decompresses to more than {} bytes", self.limit),
+            )
+        })?;
+        Ok(read)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    fn tar(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut archive = tar::Builder::new(Vec::new());
+        for (name, contents) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            archive.append_data(&mut header, name, *contents).unwrap();
+        }
+        archive.into_inner().unwrap()
+    }
+
+    fn gzip(bytes: &[u8]) -> Vec<u8> {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+        encoder.write_all(bytes).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn members_are_filtered_and_sorted_by_path() {
+        let archive = gzip(&tar(&[
+            ("b.cfg", b"hostname b\n"),
+            ("skip.txt", b"notes\n"),
+            ("a.cfg", &gzip(b"hostname a\n")),
+        ]));
+        let members = members(archive.as_slice(), |name| name.ends_with(".cfg"))
+            .unwrap()
+            .unwrap();
+        let members = members
+            .iter()
+            .map(|member| (member.name.as_str(), member.bytes.as_slice()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            members,
+            vec![
+                ("a.cfg", b"hostname a\n".as_slice()),
+                ("b.cfg", b"hostname b\n".as_slice()),
+            ]
+        );
+    }
+
+    #[test]
+    fn plain_and_compressed_files_are_not_archives() {
+        assert!(
+            members(b"hostname a\n".as_slice(), |_| true)
+                .unwrap()
+                .is_none()
+        );
+        let compressed = gzip(b"hostname a\n");
+        assert!(members(compressed.as_slice(), |_| true).unwrap().is_none());
+        assert_eq!(decompress(compressed.as_slice()).unwrap(), b"hostname a\n");
+    }
+
+    #[test]
+    fn decompression_past_the_limit_fails() {
+        let compressed = gzip(&[b'!'; 4096]);
+        let mut limited = Limited::with_limit(MultiGzDecoder::new(compressed.as_slice()), 1024);
+        let err = limited.read_to_end(&mut Vec::new()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        let mut limited = Limited::with_limit(MultiGzDecoder::new(compressed.as_slice()), 4096);
+        assert_eq!(limited.read_to_end(&mut Vec::new()).unwrap(), 4096);
+    }
+}
@@ -0,0 +1,237 @@
+use std::borrow::Cow;
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use crate::Document;
+use crate::dialect::DialectKind;
+
+/// CSI sequences such as colours and `ESC[K`, OSC titles, and character set
+/// selection.
+static ESCAPE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\x1b(?:\[[0-9;?]*[ -/]*[@-~]|\][^\x07\x1b]*(?:\x07|\x1b\\)|[()][0-9A-Za-z]|[=>])")
+        .expect("valid escape sequence regex")
+});
+
+/// Pager prompts left at the start of a line: IOS `--More--`, IOS-XR and
+/// VRP `---- More ----`, ASA `<--- More --->`, and Junos `---(more 45%)---`.
+static PAGER_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)^\s*(?:<-+ ?more ?-+>|-+ ?\(?more(?: \d+%)?\)? ?-+) ?")
+        .expect("valid pager regex")
+});
+
+/// A device prompt on its own or followed by an operational command, such
+/// as `edge1#show run`, `RP/0/RSP0/CPU0:xr1#` or `user@mx1> show
+/// configuration`. VRP prompts such as `<HUAWEI>` only count with a command
+/// after them, since a bare one reads like an XML element.
+static PROMPT_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^(?:(?:[A-Za-z0-9][\w.\-/:()@~]*[#>%$]|\[[\w.\-~@ ]+\][#$])\s*(?:(?:show|sh|more|terminal|term|admin|get|cat|exit|quit)\b.*)?|<[\w.\-]+>\s*(?:display|dis|screen-length|quit)\b.*)$",
+    )
+    .expect("valid prompt regex")
+});
+
+/// Lines `show running-config` prints before the configuration on Cisco and
+/// Arista platforms, including the IOS-XR and NX-OS timestamps.
+static IOS_BANNER_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^(?:Building configuration\.\.\.|Current configuration ?: ?\d+ bytes|(?:Mon|Tue|Wed|Thu|Fri|Sat|Sun) (?:Jan|Feb|Mar|Apr|May|Jun|Jul|Aug|Sep|Oct|Nov|Dec) +\d+ \d\d:\d\d:\d\d(?:\.\d+)? \S+)$",
+    )
+    .expect("valid IOS banner regex")
+});
+
+/// The routing-engine and `[edit]` markers Junos prints above its prompt.
+static JUNOS_MARKER_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^(?:\{(?:master|backup|primary|secondary|linecard)(?::\d+)?\}|\[edit(?: [^\]]*)?\])$",
+    )
+    .expect("valid Junos marker regex")
+});
+
+/// Remove what a terminal session adds to captured output regardless of the
+/// platform: escape sequences, backspaces and carriage returns used to redraw
+/// the line, pager prompts, and prompt lines with the Junos markers above
+/// them. Runs before detection, and returns whether any line was removed,
+/// which marks the input as a terminal capture.
+pub(crate) fn strip_session(document: &mut Document) -> bool {
+    retain_lines(document, |line| {
+        let (body, ending) = split_ending(line);
+        let mut cleaned = ESCAPE_RE.replace_all(body, "");
+        if cleaned.contains(['\x08', '\r']) {
+            cleaned = Cow::Owned(redraw(&cleaned));
+        }
+        let marker = PAGER_RE.find(&cleaned).map_or(0, |found| found.end());
+        let rest = &cleaned[marker..];
+        let trimmed = rest.trim();
+        if (marker > 0 && trimmed.is_empty())
+            || PROMPT_RE.is_match(trimmed)
+            || JUNOS_MARKER_RE.is_match(trimmed)
+        {
+            return None;
+        }
+        if marker == 0 && rest.len() == body.len() {
+            return Some(Cow::Borrowed(line));
+        }
+        Some(Cow::Owned(format!("{rest}{ending}")))
+    })
+}
+
+/// Remove the banners `show running-config` prints around a configuration
+/// of the given dialect. The closing `end` goes too, but only from a
+/// terminal capture: when `captured` is set or a banner was found, since a
+/// saved configuration keeps its `end`. Runs after detection, since the same
+/// words are configuration on other platforms: `end` closes every block on
+/// FortiOS.
+pub(crate) fn strip_banners(document: &mut Document, dialect: DialectKind, captured: bool) {
+    if !matches!(
+        dialect,
+        DialectKind::CiscoIos
+            | DialectKind::CiscoNxos
+            | DialectKind::CiscoIosXr
+            | DialectKind::AristaEos
+            | DialectKind::CiscoAsa
+    ) {
+        return;
+    }
+
+    let banners = retain_lines(document, |line| {
+        (!IOS_BANNER_RE.is_match(line.trim())).then_some(Cow::Borrowed(line))
+    });
+    if !captured && !banners {
+        return;
+    }
+    let lines = document.text.split_inclusive('\n').collect::<Vec<_>>();
+    let cut = lines
+        .iter()
+        .rposition(|line| !line.trim().is_empty())
+        .filter(|&position| lines[position].trim() == "end")
+        .map(|position| {
+            (
+                position,
+                lines[..position].iter().map(|line| line.len()).sum(),
+            )
+        });
+    if let Some((position, offset)) = cut {
+        document.text.truncate(offset);
+        document.lines.truncate(position);
+    }
+}
+
+/// Rebuild the document from the lines `keep` returns, carrying the source
+/// line number of every kept line along. Returns whether a line was dropped.
+fn retain_lines(
+    document: &mut Document,
+    mut keep: impl for<'l> FnMut(&'l str) -> Option<Cow<'l, str>>,
+) -> bool {
+    let mut text = String::with_capacity(document.text.len());
+    let mut lines = Vec::with_capacity(document.lines.len());
+    for (line, &number) in document.text.split_inclusive('\n').zip(&document.lines) {
+        if let Some(kept) = keep(line) {
+            text.push_str(&kept);
+            lines.push(number);
+        }
+    }
+    let dropped = lines.len() < document.lines.len();
+    document.text = text;
+    document.lines = lines;
+    dropped
+}
+
+fn split_ending(line: &str) -> (&str, &str) {
+    let body = line.trim_end_matches(['\r', '\n']);
+    (body, &line[body.len()..])
+}
+
+/// Replay backspaces and carriage returns the way a terminal would, so a
+/// pager prompt that was printed and then erased disappears.
+fn redraw(body: &str) -> String {
+    let mut screen: Vec<char> = Vec::with_capacity(body.len());
+    let mut cursor = 0usize;
+    for ch in body.chars() {
+        match ch {
+            '\x08' => cursor = cursor.saturating_sub(1),
+            '\r' => cursor = 0,
+            _ => {
+                if cursor < screen.len() {
+                    screen[cursor] = ch;
+                } else {
+                    screen.push(ch);
+                }
+                cursor += 1;
+            }
+        }
+    }
+    let line: String = screen.into_iter().collect();
+    line.trim_end().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document(text: &str) -> Document {
+        Document {
+            device: None,
+            text: text.to_string(),
+            lines: (1..=text.split_inclusive('\n').count()).collect(),
+        }
+    }
+
+    #[test]
+    fn session_artefacts_are_removed_with_their_line_numbers() {
+        let mut doc = document(
+            "edge1#show run\nhostname edge1\n --More-- \x08\x08\x08\x08\x08\x08\x08\x08\x08\x08          \x08\x08\x08\x08\x08\x08\x08\x08\x08\x08 description \x1b[1mcore\x1b[0m\r\n---(more 45%)---\nedge1#\n",
+        );
+        strip_session(&mut doc);
+        assert_eq!(doc.text, "hostname edge1\n description core\r\n");
+        assert_eq!(doc.lines, vec![2, 3]);
+    }
+
+    #[test]
+    fn prompts_need_a_command_or_nothing_after_them() {
+        for prompt in [
+            "edge1#",
+            "edge1# show running-config",
+            "RP/0/RSP0/CPU0:xr1#sh run",
+            "sw1(config)#",
+            "netops@mx1> show configuration | no-more",
+            "<HUAWEI>display current-configuration",
+            "[admin@fw1 ~]$ cat /config/bigip.conf",
+        ] {
+            assert!(PROMPT_RE.is_match(prompt), "{prompt}");
+        }
+        for config in [
+            "<configuration>",
+            "<HUAWEI>",
+            "set system host-name mx1",
+            "banner motd #",
+            "  }",
+        ] {
+            assert!(!PROMPT_RE.is_match(config), "{config}");
+        }
+    }
+
+    #[test]
+    fn show_banners_and_end_are_removed_only_from_cisco_captures() {
+        let text =
+            "Building configuration...\nCurrent configuration : 812 bytes\nhostname r1\nend\n\n";
+        let mut ios = document(text);
+        strip_banners(&mut ios, DialectKind::CiscoIos, false);
+        assert_eq!(ios.text, "hostname r1\n");
+        assert_eq!(ios.lines, vec![3]);
+
+        let mut captured = document("edge1#show run\nhostname r1\nend\nedge1#\n");
+        let prompts = strip_session(&mut captured);
+        strip_banners(&mut captured, DialectKind::CiscoIos, prompts);
+        assert_eq!(captured.text, "hostname r1\n");
+
+        let mut saved = document("hostname r1\nend\n");
+        assert!(!strip_session(&mut saved));
+        strip_banners(&mut saved, DialectKind::CiscoIos, false);
+        assert_eq!(saved.text, "hostname r1\nend\n");
+
+        let mut fortios = document("config system global\nend\n");
+        strip_banners(&mut fortios, DialectKind::FortinetFortios, true);
+        assert_eq!(fortios.text, "config system global\nend\n");
+    }
+}
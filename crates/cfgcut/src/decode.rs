@@ -0,0 +1,179 @@
+use encoding_rs::{Encoding, UTF_8, UTF_16BE, UTF_16LE, WINDOWS_1252};
+
+/// How much of an input is sampled to spot UTF-16 without a byte order mark.
+const UTF16_SAMPLE_LEN: usize = 4096;
+
+/// The result of turning the bytes of an input into text.
+pub(crate) enum Decoded {
+    /// The text, and how it was obtained when that was not plain UTF-8.
+    Text(String, Option<String>),
+    /// The input holds NUL bytes that do not follow the UTF-16 pattern.
+    Binary,
+}
+
+/// Decode `bytes` as `forced` when given. Otherwise a byte order mark
+/// selects UTF-8 or UTF-16, and so does the NUL byte in every other position
+/// that mostly-ASCII UTF-16 text has without one. Other input is read as
+/// UTF-8, and input that is not valid UTF-8 falls back to Windows-1252, the
+/// superset of Latin-1 that never fails. The note explains any decoding
+/// other than UTF-8, so the caller can warn about it.
+pub(crate) fn decode(bytes: &[u8], forced: Option<&'static Encoding>) -> Decoded {
+    if let Some(encoding) = forced {
+        let (text, malformed) = encoding.decode_with_bom_removal(bytes);
+        let note = malformed
+            .then(|| format!("invalid {} sequences replaced with U+FFFD", encoding.name()));
+        return Decoded::Text(text.into_owned(), note);
+    }
+
+    if let Some((encoding, bom)) = Encoding::for_bom(bytes) {
+        let (text, malformed) = encoding.decode_without_bom_handling(&bytes[bom..]);
+        let note = (encoding != UTF_8 || malformed).then(|| {
+            let mut note = format!("decoded as {} from its byte order mark", encoding.name());
+            if malformed {
+                note.push_str(", with invalid sequences replaced with U+FFFD");
+            }
+            note
+        });
+        return Decoded::Text(text.into_owned(), note);
+    }
+
+    if bytes.contains(&0) {
+        let Some(encoding) = utf16_without_bom(bytes) else {
+            return Decoded::Binary;
+        };
+        let (text, malformed) = encoding.decode_without_bom_handling(bytes);
+        if malformed || text.contains('\0') {
+            return Decoded::Binary;
+        }
+        return Decoded::Text(
+            text.into_owned(),
+            Some(format!(
+                "decoded as {} from its NUL bytes, as it has no byte order mark",
+                encoding.name()
+            )),
+        );
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(text) => Decoded::Text(text.to_string(), None),
+        Err(_) => {
+            let (text, _) = WINDOWS_1252.decode_without_bom_handling(bytes);
+            Decoded::Text(
+                text.into_owned(),
+                Some(format!(
+                    "not valid UTF-8, decoded as {}",
+                    WINDOWS_1252.name()
+                )),
+            )
+        }
+    }
+}
+
+/// UTF-16LE or UTF-16BE when, in the sampled start of `bytes`, nearly every
+/// code unit has a NUL high byte and a printable or whitespace low byte, as
+/// ASCII text does.
+fn utf16_without_bom(bytes: &[u8]) -> Option<&'static Encoding> {
+    if !bytes.len().is_multiple_of(2) {
+        return None;
+    }
+    let sample = &bytes[..bytes.len().min(UTF16_SAMPLE_LEN)];
+    let units = sample.len() / 2;
+    let (mut little, mut big) = (0, 0);
+    for pair in sample.chunks_exact(2) {
+        match pair {
+            [low, 0] if is_text(*low) => little += 1,
+            [0, low] if is_text(*low) => big += 1,
+            _ => {}
+        }
+    }
+    // Allow a few non-ASCII characters, but not a mix of both byte orders.
+    if little * 10 >= units * 9 {
+        Some(UTF_16LE)
+    } else if big * 10 >= units * 9 {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
+}
+
+fn is_text(byte: u8) -> bool {
+    matches!(byte, b'\t' | b'\n' | b'\r' | 0x20..)
+}
+
+/// Look up an encoding by any of its WHATWG labels, such as `utf-16le`,
+/// `latin1`, or `shift_jis`.
+pub(crate) fn encoding_for_label(label: &str) -> Option<&'static Encoding> {
+    Encoding::for_label(label.trim().as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(decoded: Decoded) -> (String, Option<String>) {
+        match decoded {
+            Decoded::Text(text, note) => (text, note),
+            Decoded::Binary => panic!("decoded as binary"),
+        }
+    }
+
+    #[test]
+    fn byte_order_marks_select_the_encoding() {
+        let mut utf16 = vec![0xff, 0xfe];
+        utf16.extend("hostname r1\r\n".encode_utf16().flat_map(u16::to_le_bytes));
+        assert_eq!(
+            text(decode(&utf16, None)),
+            (
+                "hostname r1\r\n".to_string(),
+                Some("decoded as UTF-16LE from its byte order mark".to_string())
+            )
+        );
+        assert_eq!(
+            text(decode(b"\xef\xbb\xbfhostname r1\n", None)),
+            ("hostname r1\n".to_string(), None)
+        );
+    }
+
+    #[test]
+    fn utf16_without_a_byte_order_mark_is_recognised_from_its_nul_bytes() {
+        let config = "hostname r1\r\n description caf\u{e9}\r\n";
+        let little = config
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            text(decode(&little, None)),
+            (
+                config.to_string(),
+                Some(
+                    "decoded as UTF-16LE from its NUL bytes, as it has no byte order mark"
+                        .to_string()
+                )
+            )
+        );
+        let big = config
+            .encode_utf16()
+            .flat_map(u16::to_be_bytes)
+            .collect::<Vec<_>>();
+        assert_eq!(text(decode(&big, None)).0, config);
+
+        assert!(matches!(decode(b"h\x00\x00\x00", None), Decoded::Binary));
+        assert!(matches!(decode(&little[1..], None), Decoded::Binary));
+    }
+
+    #[test]
+    fn invalid_utf8_falls_back_to_windows_1252() {
+        assert_eq!(
+            text(decode(b"description caf\xe9\n", None)),
+            (
+                "description caf\u{e9}\n".to_string(),
+                Some("not valid UTF-8, decoded as windows-1252".to_string())
+            )
+        );
+        assert!(matches!(decode(b"\x00\x01", None), Decoded::Binary));
+        let latin1 = encoding_for_label("latin1");
+        assert_eq!(
+            text(decode(b"caf\xe9", latin1)),
+            ("caf\u{e9}".to_string(), None)
+        );
+    }
+}
@@ -0,0 +1,37 @@
+use super::indent::{self, IndentSyntax};
+use super::shared::ParsedConfig;
+
+const VERSION_MARKER: &str = "!Version ArubaOS-CX";
+const IDIOMS: &[&str] = &[
+    "vsx",
+    "https-server vrf ",
+    "ssh server vrf ",
+    "vlan trunk allowed ",
+    "vlan access ",
+];
+const VERSION_SCORE: u32 = 95;
+const IDIOM_SCORE: u32 = 60;
+
+/// `!` lines are comments, including the `!Version` and `!export-password`
+/// header written without a space. `#` has no special meaning.
+const AOSCX: IndentSyntax = IndentSyntax {
+    comment_prefixes: &["!"],
+    unindented_lists: &[],
+};
+
+pub(super) fn score(text: &str) -> u32 {
+    let lines = indent::structural_lines(text);
+    if lines.iter().any(|line| line.starts_with(VERSION_MARKER)) {
+        return VERSION_SCORE;
+    }
+
+    let idiomatic = lines.iter().any(|line| {
+        let trimmed = line.trim_start();
+        IDIOMS.iter().any(|idiom| trimmed.starts_with(idiom))
+    });
+    if idiomatic { IDIOM_SCORE } else { 0 }
+}
+
+pub(super) fn parse(text: &str) -> ParsedConfig {
+    indent::parse_with(text, &AOSCX)
+}
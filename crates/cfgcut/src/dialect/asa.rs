@@ -0,0 +1,41 @@
+use super::indent::{self, IndentSyntax};
+use super::shared::ParsedConfig;
+
+const VERSION_MARKERS: &[&str] = &["ASA Version ", "NGFW Version "];
+const IDIOMS: &[&str] = &[
+    ": Saved",
+    "nameif ",
+    "security-level ",
+    "same-security-traffic ",
+];
+const VERSION_SCORE: u32 = 95;
+const IDIOM_SCORE: u32 = 60;
+
+/// `:` lines are the ASA's own annotations (`: Saved`, `: end`) and `!` lines
+/// are separators; both are comments. `name` entries are written unindented
+/// after `names` but still belong to it.
+const ASA: IndentSyntax = IndentSyntax {
+    comment_prefixes: &[":", "!"],
+    unindented_lists: &[("names", "name ")],
+};
+
+pub(super) fn score(text: &str) -> u32 {
+    let lines = indent::structural_lines(text);
+    if lines.iter().any(|line| {
+        VERSION_MARKERS
+            .iter()
+            .any(|marker| line.starts_with(marker))
+    }) {
+        return VERSION_SCORE;
+    }
+
+    let idiomatic = lines.iter().any(|line| {
+        let trimmed = line.trim_start();
+        IDIOMS.iter().any(|idiom| trimmed.starts_with(idiom))
+    });
+    if idiomatic { IDIOM_SCORE } else { 0 }
+}
+
+pub(super) fn parse(text: &str) -> ParsedConfig {
+    indent::parse_with(text, &ASA)
+}
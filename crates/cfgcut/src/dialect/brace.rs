@@ -1,90 +1,281 @@
-use super::shared::{LineKind, ParsedConfig};
+use super::shared::{LineKind, NodeFlags, ParsedConfig, unquoted};
 
-pub(super) fn detect(text: &str) -> bool {
-    text.lines().any(|line| {
+const MAX_SCORE: u32 = 80;
+
+/// Scores brace syntax by the share of statements that open, close, or
+/// terminate with `;`, so a stray `{` in a banner barely registers.
+pub(super) fn score(text: &str) -> u32 {
+    let mut meaningful = 0u32;
+    let mut structural = 0u32;
+    let mut saw_brace = false;
+
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with("/*") {
+            continue;
+        }
+        meaningful += 1;
+        let opens = trimmed.ends_with('{');
+        let closes = trimmed.starts_with('}');
+        saw_brace |= opens || closes;
+        if opens || closes || trimmed.ends_with(';') {
+            structural += 1;
+        }
+    }
+
+    if !saw_brace || meaningful == 0 {
+        return 0;
+    }
+    (MAX_SCORE * structural / meaningful).max(1)
+}
+
+/// Names of the blocks opened at the top level, used to tell brace dialects
+/// apart by their root statements.
+pub(super) fn top_level_blocks(text: &str) -> Vec<&str> {
+    top_level_statements(text)
+        .into_iter()
+        .map(|statement| statement.split_whitespace().next().unwrap_or(statement))
+        .collect()
+}
+
+/// The statements that open blocks at the top level, up to their brace.
+pub(super) fn top_level_statements(text: &str) -> Vec<&str> {
+    let mut depth = 0usize;
+    let mut blocks = Vec::new();
+    for line in text.lines() {
         let trimmed = line.trim();
-        trimmed.contains('{') || trimmed.starts_with('}')
-    })
+        if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with("/*") {
+            continue;
+        }
+        let scan = Scan::line(trimmed, &['"', '\'']);
+        if depth == 0
+            && let Some(pos) = scan.first_open
+        {
+            let (_, statement) = NodeFlags::strip_prefixes(trimmed[..pos].trim());
+            blocks.push(statement);
+        }
+        depth = (depth + scan.open).saturating_sub(scan.close);
+    }
+    blocks
+}
+
+/// Lexical rules that differ between brace-style dialects.
+pub(super) struct BraceSyntax {
+    /// Prefixes that mark a whole line as a comment. A `/*` comment runs
+    /// until the next `*/`, even across lines.
+    pub comment_prefixes: &'static [&'static str],
+    /// Characters that open a quoted string, inside which braces, `;` and
+    /// comment markers are literal.
+    pub quotes: &'static [char],
+    /// Strip quotes from match text.
+    pub unquote: bool,
+    /// Read `inactive:` and `protect:` prefixes as node flags instead of as
+    /// part of the statement.
+    pub node_flags: bool,
 }
 
+const JUNOS: BraceSyntax = BraceSyntax {
+    comment_prefixes: &["##", "/*"],
+    quotes: &['"'],
+    unquote: false,
+    node_flags: true,
+};
+
 pub(super) fn parse(text: &str) -> ParsedConfig {
+    parse_with(text, &JUNOS)
+}
+
+pub(super) fn parse_with(text: &str, syntax: &BraceSyntax) -> ParsedConfig {
     let mut parsed = ParsedConfig::default();
     let mut stack: Vec<usize> = Vec::new();
+    let mut open_comment: Option<(usize, usize)> = None;
 
     for line in text.lines() {
         let mut trimmed = line.trim();
+
+        if let Some((idx, opening_indent)) = open_comment {
+            // Continuation lines keep their indentation relative to `/*`.
+            let relative = leading_whitespace(line).saturating_sub(opening_indent);
+            let entry = &mut parsed.lines[idx];
+            entry.raw.push('\n');
+            entry.raw.push_str(&indented(
+                stack.len(),
+                &format!("{:relative$}{trimmed}", ""),
+            ));
+            let (body, closed) = match trimmed.split_once("*/") {
+                Some((body, _)) => (body, true),
+                None => (trimmed, false),
+            };
+            if let Some(text) = entry.match_text.as_mut()
+                && !body.trim().is_empty()
+            {
+                if !text.is_empty() {
+                    text.push(' ');
+                }
+                text.push_str(body.trim());
+            }
+            if closed {
+                open_comment = None;
+            }
+            continue;
+        }
+
         if trimmed.is_empty() {
             continue;
         }
 
         while trimmed.starts_with('}') {
             trimmed = trimmed[1..].trim_start();
-            if let Some(closed_idx) = stack.pop() {
-                let indent_spaces = stack.len() * 2;
-                parsed.push_line(
-                    format!("{:indent$}}}", "", indent = indent_spaces),
-                    None,
-                    LineKind::Closing,
-                    Some(closed_idx),
-                );
-            }
+            close(&mut parsed, &mut stack);
         }
 
         if trimmed.is_empty() {
             continue;
         }
 
-        let indent_spaces = stack.len() * 2;
-        let rendered = format!("{:indent$}{}", "", trimmed, indent = indent_spaces);
+        let rendered = indented(stack.len(), trimmed);
+        let parent = stack.last().copied();
 
-        let kind = if trimmed.starts_with("##") {
-            LineKind::Comment
-        } else {
-            LineKind::Command
-        };
+        if let Some(prefix) = syntax
+            .comment_prefixes
+            .iter()
+            .copied()
+            .find(|prefix| trimmed.starts_with(prefix))
+        {
+            let idx = parsed.push_line(
+                rendered,
+                Some(comment_text(trimmed, prefix)),
+                LineKind::Comment,
+                parent,
+            );
+            if prefix == "/*" && !trimmed[2..].contains("*/") {
+                open_comment = Some((idx, leading_whitespace(line)));
+            }
+            continue;
+        }
 
-        let match_text = if matches!(kind, LineKind::Comment) {
-            Some(trimmed.trim_start_matches('#').trim().to_string())
-        } else if let Some(pos) = trimmed.find('{') {
-            Some(trimmed[..pos].trim().to_string())
+        let scan = Scan::line(trimmed, syntax.quotes);
+        let mut statement = &trimmed[..scan.statement_end];
+        let mut flags = NodeFlags::default();
+        if syntax.node_flags {
+            (flags, statement) = NodeFlags::strip_prefixes(statement);
+        }
+        let statement = statement.trim().trim_end_matches(';').trim_end();
+        let match_text = if syntax.unquote {
+            unquoted(statement)
         } else {
-            Some(trimmed.trim_end_matches(';').trim().to_string())
+            statement.to_string()
         };
 
-        let parent = stack.last().copied();
-        let idx = parsed.push_line(rendered, match_text, kind, parent);
-
-        let open_braces = trimmed.matches('{').count();
-        let close_braces = trimmed.matches('}').count();
+        let idx = parsed.push_line(rendered, Some(match_text), LineKind::Command, parent);
+        parsed.lines[idx].flags = flags;
 
-        if open_braces > close_braces {
+        if scan.open > scan.close {
             stack.push(idx);
         }
-
-        let mut diff = close_braces.saturating_sub(open_braces);
-        while diff > 0 {
-            if let Some(closed_idx) = stack.pop() {
-                let indent_spaces = stack.len() * 2;
-                parsed.push_line(
-                    format!("{:indent$}}}", "", indent = indent_spaces),
-                    None,
-                    LineKind::Closing,
-                    Some(closed_idx),
-                );
-            }
-            diff -= 1;
+        for _ in 0..scan.close.saturating_sub(scan.open) {
+            close(&mut parsed, &mut stack);
         }
     }
 
-    while let Some(closed_idx) = stack.pop() {
-        let indent_spaces = stack.len() * 2;
+    while !stack.is_empty() {
+        close(&mut parsed, &mut stack);
+    }
+
+    parsed
+}
+
+fn indented(depth: usize, text: &str) -> String {
+    format!("{:indent$}{text}", "", indent = depth * 2)
+}
+
+fn leading_whitespace(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+fn close(parsed: &mut ParsedConfig, stack: &mut Vec<usize>) {
+    if let Some(closed_idx) = stack.pop() {
         parsed.push_line(
-            format!("{:indent$}}}", "", indent = indent_spaces),
+            indented(stack.len(), "}"),
             None,
             LineKind::Closing,
             Some(closed_idx),
         );
     }
+}
 
-    parsed
+/// Comment body without its markers; `/* ... */` loses the closing marker too.
+fn comment_text(line: &str, prefix: &str) -> String {
+    let body = line.trim_start_matches(prefix);
+    let body = if prefix == "/*" {
+        body.split_once("*/").map_or(body, |(body, _)| body)
+    } else {
+        body
+    };
+    body.trim().to_string()
+}
+
+/// One line of a brace configuration, scanned outside quoted strings.
+struct Scan {
+    open: usize,
+    close: usize,
+    first_open: Option<usize>,
+    /// End of the statement text: the first `{`, or the end of the line
+    /// before any trailing comment.
+    statement_end: usize,
+}
+
+impl Scan {
+    fn line(line: &str, quotes: &[char]) -> Self {
+        let mut scan = Self {
+            open: 0,
+            close: 0,
+            first_open: None,
+            statement_end: line.len(),
+        };
+        let mut quote: Option<char> = None;
+        let mut escaped = false;
+        let mut terminated = false;
+
+        for (pos, ch) in line.char_indices() {
+            if let Some(open) = quote {
+                if escaped {
+                    escaped = false;
+                } else if ch == '\\' {
+                    escaped = true;
+                } else if ch == open {
+                    quote = None;
+                }
+                continue;
+            }
+            match ch {
+                _ if quotes.contains(&ch) => quote = Some(ch),
+                '{' => {
+                    scan.open += 1;
+                    scan.first_open.get_or_insert(pos);
+                    terminated = true;
+                }
+                '}' => {
+                    scan.close += 1;
+                    terminated = true;
+                }
+                ';' => terminated = true,
+                // A comment may follow a complete statement, as in
+                // `encrypted-password "..."; ## SECRET-DATA`.
+                '#' if terminated => {
+                    scan.statement_end = pos;
+                    break;
+                }
+                '/' if terminated && line[pos..].starts_with("/*") => {
+                    scan.statement_end = pos;
+                    break;
+                }
+                _ => {}
+            }
+        }
+        if let Some(pos) = scan.first_open {
+            scan.statement_end = scan.statement_end.min(pos);
+        }
+        scan
+    }
 }
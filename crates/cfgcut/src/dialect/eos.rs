@@ -1,11 +1,34 @@
 use super::indent;
 use super::shared::ParsedConfig;
 
-pub(super) fn detect(text: &str) -> bool {
-    text.lines().any(|line| {
-        let trimmed = line.trim();
-        trimmed.contains("cEOS") || trimmed.contains("vEOS") || trimmed.contains("Arista")
-    })
+const PLATFORM_MARKERS: &[&str] = &["cEOS", "vEOS", "Arista"];
+const IDIOMS: &[&str] = &[
+    "service routing protocols model",
+    "management api http-commands",
+    "daemon TerminAttr",
+    "transceiver qsfp default-mode",
+];
+const MARKER_SCORE: u32 = 90;
+const IDIOM_SCORE: u32 = 25;
+const IDIOM_CAP: u32 = 75;
+
+pub(super) fn score(text: &str) -> u32 {
+    let lines = indent::structural_lines(text);
+    if lines.iter().any(|line| {
+        indent::is_header_line(line) && PLATFORM_MARKERS.iter().any(|marker| line.contains(marker))
+    }) {
+        return MARKER_SCORE;
+    }
+
+    let idioms = lines
+        .iter()
+        .map(|line| line.trim())
+        .filter(|line| IDIOMS.iter().any(|idiom| line.starts_with(idiom)))
+        .count();
+    u32::try_from(idioms)
+        .unwrap_or(u32::MAX)
+        .saturating_mul(IDIOM_SCORE)
+        .min(IDIOM_CAP)
 }
 
 pub(super) fn parse(text: &str) -> ParsedConfig {
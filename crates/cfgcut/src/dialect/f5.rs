@@ -0,0 +1,232 @@
+use super::brace;
+use super::shared::{LineKind, ParsedConfig, root_share_score, unquoted};
+
+const HEADER_MARKER: &str = "#TMSH-VERSION";
+const HEADER_SCORE: u32 = 95;
+const MODULE_SCORE: u32 = 90;
+const INDENT: usize = 4;
+
+/// tmsh modules that prefix every top-level object.
+const MODULES: &[&str] = &[
+    "analytics",
+    "apm",
+    "asm",
+    "auth",
+    "cli",
+    "cm",
+    "gtm",
+    "ilx",
+    "ltm",
+    "net",
+    "pem",
+    "security",
+    "sys",
+    "wom",
+];
+
+/// Objects whose bodies are TCL and must be kept line for line rather than
+/// split at every brace.
+const VERBATIM_BLOCKS: &[&str] = &["ltm rule ", "gtm rule ", "pem irule "];
+
+pub(super) fn score(text: &str) -> u32 {
+    if text
+        .lines()
+        .any(|line| line.trim_start().starts_with(HEADER_MARKER))
+    {
+        return HEADER_SCORE;
+    }
+    if brace::score(text) == 0 {
+        return 0;
+    }
+    // tmsh objects always name a type after the module, as in `ltm virtual
+    // /Common/vs {` or `sys global-settings {`; a lone `security {` or
+    // `system {` is Junos.
+    let roots = brace::top_level_statements(text)
+        .into_iter()
+        .map(|statement| {
+            let mut words = statement.split_whitespace();
+            match (words.next(), words.next()) {
+                (Some(module), Some(_)) => module,
+                _ => "",
+            }
+        })
+        .collect::<Vec<_>>();
+    root_share_score(&roots, MODULES, MODULE_SCORE)
+}
+
+/// tmsh nests objects such as `profiles { /Common/http { } }` on one line, so
+/// every statement between braces becomes its own node and is re-indented on
+/// output. Empty `{ }` blocks stay on their statement's line, and iRule
+/// bodies are kept verbatim.
+pub(super) fn parse(text: &str) -> ParsedConfig {
+    let mut builder = Builder::default();
+    let mut verbatim: Option<(usize, usize)> = None;
+
+    for line in text.lines() {
+        let raw = line.trim_end();
+        let trimmed = raw.trim();
+
+        if let Some((owner, depth)) = verbatim.take() {
+            let depth = tcl_depth(trimmed, depth);
+            if depth == 0 {
+                builder
+                    .parsed
+                    .push_line(raw.to_string(), None, LineKind::Closing, Some(owner));
+                builder.stack.pop();
+            } else {
+                builder.parsed.push_line(
+                    raw.to_string(),
+                    Some(trimmed.to_string()),
+                    LineKind::Command,
+                    Some(owner),
+                );
+                verbatim = Some((owner, depth));
+            }
+            continue;
+        }
+
+        if trimmed.is_empty() {
+            continue;
+        }
+        if let Some(comment) = trimmed.strip_prefix('#') {
+            builder.parsed.push_line(
+                builder.indented(trimmed),
+                Some(comment.trim().to_string()),
+                LineKind::Comment,
+                builder.stack.last().copied(),
+            );
+            continue;
+        }
+
+        builder.push_statements(trimmed);
+
+        if trimmed.ends_with('{')
+            && let Some(&owner) = builder.stack.last()
+            && builder.parsed.lines[owner]
+                .match_text
+                .as_deref()
+                .is_some_and(|text| VERBATIM_BLOCKS.iter().any(|block| text.starts_with(block)))
+        {
+            verbatim = Some((owner, 1));
+        }
+    }
+
+    while !builder.stack.is_empty() {
+        builder.close();
+    }
+
+    builder.parsed
+}
+
+#[derive(Default)]
+struct Builder {
+    parsed: ParsedConfig,
+    stack: Vec<usize>,
+}
+
+impl Builder {
+    fn indented(&self, text: &str) -> String {
+        format!("{:indent$}{text}", "", indent = self.stack.len() * INDENT)
+    }
+
+    /// Split one line at its braces, pushing a node for every statement.
+    fn push_statements(&mut self, line: &str) {
+        let mut pending = String::new();
+        let mut chars = line.chars().peekable();
+        let mut quote = false;
+
+        while let Some(ch) = chars.next() {
+            if quote {
+                pending.push(ch);
+                if ch == '\\' {
+                    if let Some(escaped) = chars.next() {
+                        pending.push(escaped);
+                    }
+                } else if ch == '"' {
+                    quote = false;
+                }
+                continue;
+            }
+            match ch {
+                '"' => {
+                    quote = true;
+                    pending.push(ch);
+                }
+                '{' => {
+                    let statement = std::mem::take(&mut pending);
+                    let statement = statement.trim();
+                    while chars.peek().is_some_and(|c| c.is_whitespace()) {
+                        chars.next();
+                    }
+                    if chars.peek() == Some(&'}') {
+                        chars.next();
+                        self.push_leaf(&format!("{statement} {{ }}"), statement);
+                    } else {
+                        self.open(statement);
+                    }
+                }
+                '}' => {
+                    self.flush(&mut pending);
+                    self.close();
+                }
+                _ => pending.push(ch),
+            }
+        }
+        self.flush(&mut pending);
+    }
+
+    fn flush(&mut self, pending: &mut String) {
+        let statement = std::mem::take(pending);
+        let statement = statement.trim();
+        if !statement.is_empty() {
+            self.push_leaf(statement, statement);
+        }
+    }
+
+    fn push_leaf(&mut self, rendered: &str, statement: &str) {
+        self.parsed.push_line(
+            self.indented(rendered),
+            Some(unquoted(statement)),
+            LineKind::Command,
+            self.stack.last().copied(),
+        );
+    }
+
+    fn open(&mut self, statement: &str) {
+        let rendered = if statement.is_empty() {
+            String::from("{")
+        } else {
+            format!("{statement} {{")
+        };
+        let idx = self.parsed.push_line(
+            self.indented(&rendered),
+            Some(unquoted(statement)),
+            LineKind::Command,
+            self.stack.last().copied(),
+        );
+        self.stack.push(idx);
+    }
+
+    fn close(&mut self) {
+        if let Some(owner) = self.stack.pop() {
+            self.parsed
+                .push_line(self.indented("}"), None, LineKind::Closing, Some(owner));
+        }
+    }
+}
+
+/// Track TCL brace depth, which ignores quotes but honours backslash escapes.
+fn tcl_depth(line: &str, mut depth: usize) -> usize {
+    let mut chars = line.chars();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\\' => {
+                chars.next();
+            }
+            '{' => depth += 1,
+            '}' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    depth
+}
@@ -0,0 +1,89 @@
+use super::shared::{LineKind, ParsedConfig, unquoted};
+
+const HEADER_MARKER: &str = "#config-version=";
+const HEADER_SCORE: u32 = 95;
+const STRUCTURE_SCORE: u32 = 60;
+
+pub(super) fn score(text: &str) -> u32 {
+    let mut configs = 0usize;
+    let mut closers = 0usize;
+
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with(HEADER_MARKER) {
+            return HEADER_SCORE;
+        }
+        if trimmed.starts_with("config ") {
+            configs += 1;
+        } else if trimmed == "end" || trimmed == "next" {
+            closers += 1;
+        }
+    }
+
+    if configs > 0 && closers >= configs {
+        STRUCTURE_SCORE
+    } else {
+        0
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Frame {
+    Config,
+    Edit,
+}
+
+/// Builds the hierarchy from `config`/`edit` openers and `next`/`end`
+/// closers. Indentation is ignored because FortiOS does not rely on it.
+pub(super) fn parse(text: &str) -> ParsedConfig {
+    let mut parsed = ParsedConfig::default();
+    let mut stack: Vec<(Frame, usize)> = Vec::new();
+
+    for line in text.lines() {
+        let raw = line.trim_end();
+        let trimmed = raw.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+
+        if trimmed.starts_with('#') {
+            parsed.push_line(
+                raw.to_string(),
+                Some(trimmed.trim_start_matches('#').trim().to_string()),
+                LineKind::Comment,
+                stack.last().map(|&(_, idx)| idx),
+            );
+            continue;
+        }
+
+        let closes = match trimmed {
+            "next" => Some(Frame::Edit),
+            "end" => Some(Frame::Config),
+            _ => None,
+        };
+        if let Some(frame) = closes
+            && let Some(pos) = stack.iter().rposition(|&(open, _)| open == frame)
+        {
+            let (_, owner) = stack[pos];
+            stack.truncate(pos);
+            parsed.push_line(raw.to_string(), None, LineKind::Closing, Some(owner));
+            continue;
+        }
+
+        let parent = stack.last().map(|&(_, idx)| idx);
+        let idx = parsed.push_line(
+            raw.to_string(),
+            Some(unquoted(trimmed)),
+            LineKind::Command,
+            parent,
+        );
+
+        if trimmed.starts_with("config ") {
+            stack.push((Frame::Config, idx));
+        } else if trimmed.starts_with("edit ") {
+            stack.push((Frame::Edit, idx));
+        }
+    }
+
+    parsed
+}
@@ -1,17 +1,70 @@
-use super::shared::{
-    LineKind, ParsedConfig, dialect_comment_prefix, extract_match_text, is_comment,
-};
+use super::shared::{LineKind, ParsedConfig, extract_match_text};
+
+/// Baseline score for any indent-style configuration. Kept low so that
+/// platform-specific markers always outrank it.
+pub(super) const GENERIC_SCORE: u32 = 5;
 
-pub(super) fn detect(text: &str) -> bool {
-    text.lines().any(|line| {
+pub(super) fn score(text: &str) -> u32 {
+    let generic = structural_lines(text).into_iter().any(|line| {
         line.trim_start().starts_with('!') || line.trim_start().starts_with("interface ")
-    })
+    });
+    if generic { GENERIC_SCORE } else { 0 }
+}
+
+/// Prefixes of the header lines `show running-config` prints above the
+/// configuration. Platform names are only trusted there: a `description`
+/// naming a neighbour says nothing about the device itself.
+const HEADER_PREFIXES: &[&str] = &["!Command:", "! Command:", "! device:", "!Time:", "version "];
+
+/// Whether `line` is an unindented `show running-config` header line.
+pub(super) fn is_header_line(line: &str) -> bool {
+    HEADER_PREFIXES
+        .iter()
+        .any(|prefix| line.starts_with(prefix))
+}
+
+/// Lines that carry configuration structure, skipping delimited banner bodies
+/// so free-form banner text cannot masquerade as platform markers.
+pub(super) fn structural_lines(text: &str) -> Vec<&str> {
+    let mut lines = Vec::new();
+    let mut delimiter: Option<&str> = None;
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if let Some(current) = delimiter {
+            if trimmed == current {
+                delimiter = None;
+            }
+            continue;
+        }
+        lines.push(line);
+        delimiter = banner_delimiter(trimmed);
+    }
+    lines
+}
+
+/// Lexical rules that differ between indent-style dialects.
+pub(super) struct IndentSyntax {
+    /// Prefixes that mark a whole line as a comment.
+    pub comment_prefixes: &'static [&'static str],
+    /// Header statements whose members follow without indentation, as
+    /// `(header, member prefix)` pairs.
+    pub unindented_lists: &'static [(&'static str, &'static str)],
 }
 
+const IOS: IndentSyntax = IndentSyntax {
+    comment_prefixes: &["!", "#"],
+    unindented_lists: &[],
+};
+
 pub(super) fn parse(text: &str) -> ParsedConfig {
+    parse_with(text, &IOS)
+}
+
+pub(super) fn parse_with(text: &str, syntax: &IndentSyntax) -> ParsedConfig {
     let mut parsed = ParsedConfig::default();
     let mut stack: Vec<(usize, usize)> = Vec::new();
     let mut multiline: Option<(usize, String)> = None;
+    let mut list: Option<(usize, &str)> = None;
 
     for line in text.lines() {
         let trimmed_end = line.trim_end();
@@ -52,16 +105,29 @@ pub(super) fn parse(text: &str) -> ParsedConfig {
             }
         }
 
-        let parent = stack.last().map(|&(_, idx)| idx);
-        let mut match_text = Some(extract_match_text(
-            trimmed_end,
-            dialect_comment_prefix(trimmed_end),
-        ));
-        let kind = if is_comment(trimmed_end) {
+        let comment_prefix = syntax
+            .comment_prefixes
+            .iter()
+            .copied()
+            .find(|prefix| trimmed.starts_with(prefix));
+        let mut match_text = Some(extract_match_text(trimmed_end, comment_prefix));
+        let kind = if comment_prefix.is_some() {
             LineKind::Comment
         } else {
             LineKind::Command
         };
+
+        if indent == 0 && matches!(kind, LineKind::Command) {
+            match list {
+                Some((header, member)) if trimmed.starts_with(member) => {
+                    parsed.push_line(trimmed_end.to_string(), match_text, kind, Some(header));
+                    continue;
+                }
+                _ => list = None,
+            }
+        }
+
+        let parent = stack.last().map(|&(_, idx)| idx);
         if matches!(kind, LineKind::Command)
             && let Some(text) = banner_match_text(trimmed)
         {
@@ -71,6 +137,15 @@ pub(super) fn parse(text: &str) -> ParsedConfig {
         let idx = parsed.push_line(trimmed_end.to_string(), match_text, kind, parent);
         stack.push((indent, idx));
 
+        if indent == 0
+            && let Some(&(_, member)) = syntax
+                .unindented_lists
+                .iter()
+                .find(|(header, _)| trimmed == *header)
+        {
+            list = Some((idx, member));
+        }
+
         if let Some(delimiter) = banner_delimiter(trimmed) {
             multiline = Some((idx, delimiter.to_string()));
         }
@@ -79,7 +154,7 @@ pub(super) fn parse(text: &str) -> ParsedConfig {
     parsed
 }
 
-fn banner_delimiter(line: &str) -> Option<&str> {
+pub(super) fn banner_delimiter(line: &str) -> Option<&str> {
     let mut parts = line.split_whitespace();
     match parts.next() {
         Some(word) if word.eq_ignore_ascii_case("banner") => {}
@@ -93,7 +168,7 @@ fn banner_delimiter(line: &str) -> Option<&str> {
     Some(candidate)
 }
 
-fn banner_match_text(line: &str) -> Option<String> {
+pub(super) fn banner_match_text(line: &str) -> Option<String> {
     let mut parts = line.split_whitespace();
     let command = parts.next()?;
     if !command.eq_ignore_ascii_case("banner") {
@@ -1,8 +1,25 @@
 use super::indent;
 use super::shared::ParsedConfig;
 
-pub(super) fn detect(text: &str) -> bool {
-    indent::detect(text)
+const MARKERS: &[&str] = &[
+    "Current configuration :",
+    "boot-start-marker",
+    "service password-encryption",
+    "service timestamps",
+];
+const MARKER_SCORE: u32 = 30;
+
+pub(super) fn score(text: &str) -> u32 {
+    let marked = indent::structural_lines(text).into_iter().any(|line| {
+        MARKERS
+            .iter()
+            .any(|marker| line.trim_start().starts_with(marker))
+    });
+    if marked {
+        MARKER_SCORE
+    } else {
+        indent::score(text)
+    }
 }
 
 pub(super) fn parse(text: &str) -> ParsedConfig {
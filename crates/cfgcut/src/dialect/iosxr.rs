@@ -0,0 +1,264 @@
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use super::indent;
+use super::shared::{
+    LineKind, ParsedConfig, dialect_comment_prefix, extract_match_text, is_comment,
+};
+
+static PROMPT_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\bRP/\d+/[A-Z0-9]+/CPU\d+:").expect("valid prompt regex"));
+
+const PLATFORM_MARKERS: &[&str] = &["!! IOS XR Configuration", "!! Last configuration change"];
+const MARKER_SCORE: u32 = 90;
+const TERMINATOR_SCORE: u32 = 40;
+const TERMINATOR_CAP: u32 = 80;
+
+/// Top-level blocks whose extent is defined by a closing keyword rather than
+/// by indentation.
+const KEYWORD_BLOCKS: &[(&str, &str)] = &[
+    ("route-policy ", "end-policy"),
+    ("prefix-set ", "end-set"),
+    ("community-set ", "end-set"),
+    ("extcommunity-set ", "end-set"),
+    ("large-community-set ", "end-set"),
+    ("as-path-set ", "end-set"),
+    ("rd-set ", "end-set"),
+    ("tag-set ", "end-set"),
+    ("group ", "end-group"),
+];
+
+pub(super) fn score(text: &str) -> u32 {
+    let lines = indent::structural_lines(text);
+    if lines.iter().any(|line| {
+        PLATFORM_MARKERS
+            .iter()
+            .any(|marker| line.starts_with(marker))
+            || PROMPT_RE.is_match(line)
+    }) {
+        return MARKER_SCORE;
+    }
+
+    let terminators = lines
+        .iter()
+        .filter(|line| {
+            KEYWORD_BLOCKS
+                .iter()
+                .any(|(_, terminator)| line.trim_end() == *terminator)
+        })
+        .count();
+    u32::try_from(terminators)
+        .unwrap_or(u32::MAX)
+        .saturating_mul(TERMINATOR_SCORE)
+        .min(TERMINATOR_CAP)
+}
+
+/// State for a keyword-terminated block such as `route-policy` or `prefix-set`.
+struct KeywordBlock {
+    opener: usize,
+    terminator: &'static str,
+    /// Open `if` statements paired with the branch currently receiving children.
+    conditionals: Vec<(usize, usize)>,
+}
+
+impl KeywordBlock {
+    fn current_parent(&self) -> usize {
+        self.conditionals
+            .last()
+            .map_or(self.opener, |&(_, branch)| branch)
+    }
+}
+
+pub(super) fn parse(text: &str) -> ParsedConfig {
+    let mut parsed = ParsedConfig::default();
+    let mut stack: Vec<(usize, usize)> = Vec::new();
+    let mut block: Option<KeywordBlock> = None;
+    let mut multiline: Option<(usize, String)> = None;
+
+    for line in text.lines() {
+        let trimmed_end = line.trim_end();
+        let trimmed = trimmed_end.trim();
+
+        if let Some((parent_idx, delimiter)) = multiline.take() {
+            let is_closing = trimmed == delimiter;
+            let (kind, match_text) = if is_closing {
+                (LineKind::Closing, None)
+            } else {
+                (LineKind::Command, Some(trimmed.to_string()))
+            };
+            parsed.push_line(trimmed_end.to_string(), match_text, kind, Some(parent_idx));
+            if !is_closing {
+                multiline = Some((parent_idx, delimiter));
+            }
+            continue;
+        }
+
+        if trimmed.is_empty() {
+            continue;
+        }
+
+        if let Some(current) = block.as_mut() {
+            if trimmed == current.terminator {
+                parsed.push_line(
+                    trimmed_end.to_string(),
+                    None,
+                    LineKind::Closing,
+                    Some(current.opener),
+                );
+                block = None;
+            } else {
+                push_block_line(&mut parsed, current, trimmed_end);
+            }
+            continue;
+        }
+
+        let indent = trimmed_end
+            .chars()
+            .take_while(|c| c.is_whitespace())
+            .count();
+
+        if trimmed == "!" {
+            push_separator(&mut parsed, &mut stack, trimmed_end, indent);
+            continue;
+        }
+
+        while let Some(&(prev_indent, _)) = stack.last() {
+            if indent <= prev_indent {
+                stack.pop();
+            } else {
+                break;
+            }
+        }
+
+        let parent = stack.last().map(|&(_, idx)| idx);
+        let kind = if is_comment(trimmed_end) {
+            LineKind::Comment
+        } else {
+            LineKind::Command
+        };
+        let mut match_text = Some(extract_match_text(
+            trimmed_end,
+            dialect_comment_prefix(trimmed_end),
+        ));
+        if matches!(kind, LineKind::Command)
+            && let Some(text) = indent::banner_match_text(trimmed)
+        {
+            match_text = Some(text);
+        }
+
+        let idx = parsed.push_line(trimmed_end.to_string(), match_text, kind, parent);
+
+        if indent == 0
+            && matches!(kind, LineKind::Command)
+            && let Some(terminator) = terminator_for(trimmed)
+        {
+            stack.clear();
+            block = Some(KeywordBlock {
+                opener: idx,
+                terminator,
+                conditionals: Vec::new(),
+            });
+            continue;
+        }
+
+        stack.push((indent, idx));
+
+        if let Some(delimiter) = indent::banner_delimiter(trimmed) {
+            multiline = Some((idx, delimiter.to_string()));
+        }
+    }
+
+    parsed
+}
+
+fn terminator_for(line: &str) -> Option<&'static str> {
+    KEYWORD_BLOCKS
+        .iter()
+        .find(|(opener, _)| line.starts_with(opener))
+        .map(|&(_, terminator)| terminator)
+}
+
+/// A bare `!` closes the block opened at the same indentation. When there is
+/// no such block it is a plain separator and stays a comment.
+fn push_separator(
+    parsed: &mut ParsedConfig,
+    stack: &mut Vec<(usize, usize)>,
+    raw: &str,
+    indent: usize,
+) {
+    while let Some(&(prev_indent, _)) = stack.last() {
+        if prev_indent > indent {
+            stack.pop();
+        } else {
+            break;
+        }
+    }
+
+    if let Some(&(prev_indent, idx)) = stack.last()
+        && prev_indent == indent
+        && !parsed.children[idx].is_empty()
+    {
+        parsed.push_line(raw.to_string(), None, LineKind::Closing, Some(idx));
+        stack.pop();
+        return;
+    }
+
+    while let Some(&(prev_indent, _)) = stack.last() {
+        if indent <= prev_indent {
+            stack.pop();
+        } else {
+            break;
+        }
+    }
+    let parent = stack.last().map(|&(_, idx)| idx);
+    parsed.push_line(
+        raw.to_string(),
+        Some(String::new()),
+        LineKind::Comment,
+        parent,
+    );
+}
+
+fn push_block_line(parsed: &mut ParsedConfig, block: &mut KeywordBlock, raw: &str) {
+    let trimmed = raw.trim();
+    let parent = block.current_parent();
+
+    if is_comment(raw) {
+        parsed.push_line(
+            raw.to_string(),
+            Some(extract_match_text(raw, dialect_comment_prefix(raw))),
+            LineKind::Comment,
+            Some(parent),
+        );
+        return;
+    }
+
+    // Set entries are comma-separated; the separator is not part of the value.
+    let match_text = Some(trimmed.trim_end_matches(',').trim_end().to_string());
+
+    if trimmed == "endif"
+        && let Some((if_idx, _)) = block.conditionals.pop()
+    {
+        parsed.push_line(raw.to_string(), None, LineKind::Closing, Some(if_idx));
+        return;
+    }
+
+    if (trimmed == "else" || trimmed.starts_with("elseif "))
+        && let Some(frame) = block.conditionals.last_mut()
+    {
+        let idx = parsed.push_line(
+            raw.to_string(),
+            match_text,
+            LineKind::Command,
+            Some(frame.0),
+        );
+        frame.1 = idx;
+        return;
+    }
+
+    let idx = parsed.push_line(raw.to_string(), match_text, LineKind::Command, Some(parent));
+    if trimmed.starts_with("if ") && trimmed.ends_with(" then") {
+        block.conditionals.push((idx, idx));
+    }
+}
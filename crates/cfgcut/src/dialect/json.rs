@@ -0,0 +1,248 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use serde::de::{Deserialize, Deserializer, IgnoredAny, MapAccess, Visitor};
+use serde_json::{Map, Value};
+
+use super::shared::{Layout, LineKind, ParsedConfig};
+
+const INDENT: usize = 2;
+
+/// Members that name an entry of a keyed list, in the order they are tried.
+/// Entries without one are addressed by their position.
+const LIST_KEYS: &[&str] = &[
+    "name",
+    "index",
+    "id",
+    "sequence-id",
+    "identifier",
+    "prefix",
+    "address",
+    "ip",
+];
+
+/// Parse a JSON document, or `None` when the text is not a JSON object or
+/// array.
+pub(super) fn document(text: &str) -> Option<Value> {
+    let trimmed = text.trim_start();
+    if !trimmed.starts_with('{') && !trimmed.starts_with('[') {
+        return None;
+    }
+    serde_json::from_str(trimmed).ok()
+}
+
+/// The member names of a top-level JSON object, in document order, or `None`
+/// when the text is not a JSON object. Values are checked but not built, so
+/// detection does not pay for a full parse of every input.
+pub(super) fn top_level_keys(text: &str) -> Option<Vec<String>> {
+    let trimmed = text.trim_start();
+    if !trimmed.starts_with('{') {
+        return None;
+    }
+    let mut deserializer = serde_json::Deserializer::from_str(trimmed);
+    let TopLevelKeys(keys) = TopLevelKeys::deserialize(&mut deserializer).ok()?;
+    deserializer.end().ok()?;
+    Some(keys)
+}
+
+struct TopLevelKeys(Vec<String>);
+
+impl<'de> Deserialize<'de> for TopLevelKeys {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_map(TopLevelKeysVisitor)
+    }
+}
+
+struct TopLevelKeysVisitor;
+
+impl<'de> Visitor<'de> for TopLevelKeysVisitor {
+    type Value = TopLevelKeys;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a JSON object")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut keys = Vec::new();
+        while let Some(key) = map.next_key::<String>()? {
+            map.next_value::<IgnoredAny>()?;
+            keys.push(key);
+        }
+        Ok(TopLevelKeys(keys))
+    }
+}
+
+/// Object keys become nodes matched by their name, and list entries are
+/// matched by their key member (see [`LIST_KEYS`]). Scalar values are hidden
+/// attribute children of their key, so `PORT||Ethernet0||mtu||9100` works.
+/// Text that is not valid JSON yields an empty configuration.
+pub(super) fn parse(text: &str) -> ParsedConfig {
+    let mut parsed = ParsedConfig::default();
+    match document(text) {
+        Some(Value::Object(map)) => {
+            parsed.layout = Layout::Json { root_array: false };
+            push_members(&mut parsed, &map, None, 1);
+        }
+        Some(Value::Array(items)) => {
+            parsed.layout = Layout::Json { root_array: true };
+            push_items(&mut parsed, &items, None, 1);
+        }
+        _ => {}
+    }
+    parsed
+}
+
+fn push_members(
+    parsed: &mut ParsedConfig,
+    map: &Map<String, Value>,
+    parent: Option<usize>,
+    depth: usize,
+) {
+    for (key, value) in map {
+        let label = serde_json::to_string(key).unwrap_or_default();
+        push_value(
+            parsed,
+            &format!("{label}: "),
+            key.clone(),
+            value,
+            parent,
+            depth,
+        );
+    }
+}
+
+fn push_items(parsed: &mut ParsedConfig, items: &[Value], parent: Option<usize>, depth: usize) {
+    for (position, item) in items.iter().enumerate() {
+        let name = match item {
+            Value::Object(map) => list_key(map).unwrap_or_else(|| position.to_string()),
+            Value::Array(_) => position.to_string(),
+            scalar => scalar_text(scalar),
+        };
+        push_value(parsed, "", name, item, parent, depth);
+    }
+}
+
+fn push_value(
+    parsed: &mut ParsedConfig,
+    prefix: &str,
+    name: String,
+    value: &Value,
+    parent: Option<usize>,
+    depth: usize,
+) {
+    let indent = " ".repeat(depth * INDENT);
+    let (open, close) = match value {
+        Value::Object(map) if !map.is_empty() => ('{', '}'),
+        Value::Array(items) if !items.is_empty() => ('[', ']'),
+        _ => {
+            let rendered = serde_json::to_string(value).unwrap_or_default();
+            let idx = parsed.push_line(
+                format!("{indent}{prefix}{rendered}"),
+                Some(name),
+                LineKind::Command,
+                parent,
+            );
+            // Members expose their value as a segment; list entries are
+            // already named by it.
+            if !prefix.is_empty() && !value.is_object() && !value.is_array() {
+                parsed.push_line(
+                    String::new(),
+                    Some(scalar_text(value)),
+                    LineKind::Attribute,
+                    Some(idx),
+                );
+            }
+            return;
+        }
+    };
+
+    let idx = parsed.push_line(
+        format!("{indent}{prefix}{open}"),
+        Some(name),
+        LineKind::Command,
+        parent,
+    );
+    match value {
+        Value::Object(map) => push_members(parsed, map, Some(idx), depth + 1),
+        Value::Array(items) => push_items(parsed, items, Some(idx), depth + 1),
+        _ => {}
+    }
+    parsed.push_line(
+        format!("{indent}{close}"),
+        None,
+        LineKind::Closing,
+        Some(idx),
+    );
+}
+
+fn list_key(map: &Map<String, Value>) -> Option<String> {
+    LIST_KEYS.iter().find_map(|key| match map.get(*key) {
+        Some(value @ (Value::String(_) | Value::Number(_) | Value::Bool(_))) => {
+            Some(scalar_text(value))
+        }
+        _ => None,
+    })
+}
+
+/// Strings without their quotes; everything else as written.
+fn scalar_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Lines that need a trailing comma: the last line of every selected member
+/// that is followed by a selected sibling.
+pub(crate) fn separators(config: &ParsedConfig, ordered: &[usize]) -> HashSet<usize> {
+    let mut seen_parents = HashSet::new();
+    let mut separated = HashSet::new();
+
+    for &idx in ordered.iter().rev() {
+        let line = &config.lines[idx];
+        if !matches!(line.kind, LineKind::Command) {
+            continue;
+        }
+        if !seen_parents.insert(line.parent) {
+            let closing = config.children[idx]
+                .iter()
+                .copied()
+                .find(|&child| matches!(config.lines[child].kind, LineKind::Closing));
+            separated.insert(closing.unwrap_or(idx));
+        }
+    }
+
+    separated
+}
+
+/// Add the key member of every selected list entry, so an extracted entry
+/// still says which one it is.
+pub(crate) fn with_list_keys(config: &ParsedConfig, ordered: &[usize]) -> Vec<usize> {
+    let selected = ordered.iter().copied().collect::<HashSet<_>>();
+    let mut expanded = Vec::with_capacity(ordered.len());
+    for &idx in ordered {
+        expanded.push(idx);
+        if let Some(key) = list_key_member(config, idx)
+            && !selected.contains(&key)
+        {
+            expanded.push(key);
+        }
+    }
+    expanded
+}
+
+fn list_key_member(config: &ParsedConfig, idx: usize) -> Option<usize> {
+    let parent = config.lines[idx].parent?;
+    if config.lines[idx].raw.trim() != "{" || !config.lines[parent].raw.ends_with('[') {
+        return None;
+    }
+    let children = &config.children[idx];
+    LIST_KEYS.iter().find_map(|key| {
+        children.iter().copied().find(|&child| {
+            config.lines[child].match_text.as_deref() == Some(*key)
+                && config.children[child]
+                    .iter()
+                    .any(|&value| matches!(config.lines[value].kind, LineKind::Attribute))
+        })
+    })
+}
@@ -1,8 +1,21 @@
 use super::brace;
+use super::junos_set;
 use super::shared::ParsedConfig;
+use super::vyos;
 
-pub(super) fn detect(text: &str) -> bool {
-    brace::detect(text)
+/// Brace configurations whose top-level blocks are not Junos statements, or
+/// that carry VyOS markers, are only weak evidence for Junos.
+const FOREIGN_ROOT_DIVISOR: u32 = 4;
+
+pub(super) fn score(text: &str) -> u32 {
+    let score = brace::score(text);
+    let blocks = brace::top_level_blocks(text);
+    let foreign =
+        !blocks.is_empty() && !blocks.iter().any(|block| junos_set::ROOTS.contains(block));
+    if score > 0 && (foreign || vyos::score(text) > 0) {
+        return (score / FOREIGN_ROOT_DIVISOR).max(1);
+    }
+    score
 }
 
 pub(super) fn parse(text: &str) -> ParsedConfig {
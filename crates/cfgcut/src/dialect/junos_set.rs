@@ -1,6 +1,7 @@
-use std::collections::HashMap;
-
-use super::shared::{LineKind, ParsedConfig};
+use super::shared::{
+    ParsedConfig, SetSegment, build_set_tree, root_share_score, set_statement_roots, split_tokens,
+};
+use super::vyos_set;
 
 const KEYWORDS_WITH_VALUE: &[&str] = &[
     "unit",
@@ -26,105 +27,86 @@ const KEYWORDS_WITH_VALUE: &[&str] = &[
 ];
 const KEYWORDS_REST: &[&str] = &["description"]; // join rest of line
 
-pub(super) fn detect(text: &str) -> bool {
-    let mut saw_set = false;
+/// Top-level statements of the Junos configuration hierarchy.
+pub(super) const ROOTS: &[&str] = &[
+    "access",
+    "accounting-options",
+    "applications",
+    "apply-groups",
+    "bridge-domains",
+    "chassis",
+    "class-of-service",
+    "event-options",
+    "firewall",
+    "forwarding-options",
+    "groups",
+    "interfaces",
+    "logical-systems",
+    "multi-chassis",
+    "poe",
+    "policy-options",
+    "protocols",
+    "routing-instances",
+    "routing-options",
+    "security",
+    "services",
+    "snmp",
+    "switch-options",
+    "system",
+    "version",
+    "virtual-chassis",
+    "vlans",
+];
+const SET_SCORE: u32 = 90;
+/// Set syntax with unfamiliar roots is still more likely Junos than anything else.
+const GENERIC_SET_SCORE: u32 = 10;
 
-    for raw in text.lines() {
-        let trimmed = raw.trim();
-        if trimmed.is_empty() {
-            continue;
-        }
-        if trimmed.starts_with('#') {
-            continue;
-        }
-        if trimmed.starts_with("set ") {
-            saw_set = true;
-            continue;
-        }
-        // Encountered a meaningful line that does not begin with `set`, so this cannot
-        // be Junos set syntax.
-        return false;
+pub(super) fn score(text: &str) -> u32 {
+    // VyOS shares `interfaces`, `protocols`, and `system` with Junos.
+    if vyos_set::score(text) > 0 {
+        return GENERIC_SET_SCORE;
     }
-
-    saw_set
+    set_statement_roots(text).map_or(0, |roots| {
+        root_share_score(&roots, ROOTS, SET_SCORE).max(GENERIC_SET_SCORE)
+    })
 }
 
 pub(super) fn parse(text: &str) -> ParsedConfig {
-    let mut parsed = ParsedConfig::default();
-    let mut node_map: HashMap<Vec<String>, usize> = HashMap::new();
+    build_set_tree(text, split_segments)
+}
 
-    for raw_line in text.lines() {
-        let trimmed = raw_line.trim_end();
-        if trimmed.trim().is_empty() {
-            continue;
-        }
-        let trimmed_start = trimmed.trim_start();
-        if trimmed_start.starts_with('#') {
-            let idx = parsed.push_line(
-                trimmed_start.to_string(),
-                Some(trimmed_start.trim_start_matches('#').trim().to_string()),
-                LineKind::Comment,
-                None,
-            );
-            node_map.insert(vec![format!("comment:{idx}")], idx);
-            continue;
-        }
-        if !trimmed_start.starts_with("set ") {
-            continue;
-        }
-        let body = trimmed_start.trim_start_matches("set ");
-        let segments = split_segments(body);
-        if segments.is_empty() {
+fn split_segments(body: &str) -> Vec<SetSegment> {
+    let mut segments: Vec<SetSegment> = Vec::new();
+    let mut words = split_tokens(body).into_iter().map(|token| token.raw);
+    while let Some(word) = words.next() {
+        // A quoted string or `[ ... ]` list is always the value of the
+        // statement before it.
+        if word.starts_with(['"', '['])
+            && let Some(last) = segments.last_mut()
+        {
+            *last = SetSegment::plain(format!("{} {word}", last.text));
             continue;
         }
 
-        let mut path = Vec::new();
-        let mut parent = None;
-        for (idx, segment) in segments.iter().enumerate() {
-            path.push(segment.clone());
-            if let Some(&existing) = node_map.get(&path) {
-                parent = Some(existing);
-                continue;
-            }
-
-            let raw = if idx + 1 == segments.len() {
-                trimmed_start.to_string()
-            } else {
-                format!("set {}", path.join(" "))
-            };
-            let match_text = Some(segment.clone());
-            let node_idx = parsed.push_line(raw, match_text, LineKind::Command, parent);
-            node_map.insert(path.clone(), node_idx);
-            parent = Some(node_idx);
-        }
-    }
-
-    parsed
-}
-
-fn split_segments(body: &str) -> Vec<String> {
-    let mut segments = Vec::new();
-    let mut tokens = body.split_whitespace();
-    while let Some(token) = tokens.next() {
-        if KEYWORDS_REST.contains(&token) {
-            let mut segment = token.to_string();
-            let remainder = tokens.collect::<Vec<_>>();
+        if KEYWORDS_REST.contains(&word.as_str()) {
+            let mut segment = word;
+            let remainder = words.collect::<Vec<_>>();
             if !remainder.is_empty() {
                 segment.push(' ');
                 segment.push_str(&remainder.join(" "));
             }
-            segments.push(segment);
+            segments.push(SetSegment::plain(segment));
             break;
         }
 
-        if KEYWORDS_WITH_VALUE.contains(&token)
-            && let Some(next) = tokens.next()
+        if KEYWORDS_WITH_VALUE.contains(&word.as_str())
+            && let Some(next) = words.next()
         {
-            segments.push(format!("{token} {next}"));
+            segments.push(SetSegment::plain(format!("{word} {next}")));
             continue;
         }
 
-        segments.push(token.to_string());
+        segments.push(SetSegment::plain(word));
     }
     segments
 }
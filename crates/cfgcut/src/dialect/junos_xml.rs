@@ -0,0 +1,320 @@
+use quick_xml::Reader;
+use quick_xml::escape::{escape, resolve_predefined_entity};
+use quick_xml::events::{BytesStart, Event};
+
+use super::shared::{Layout, LineKind, NodeFlags, ParsedConfig};
+
+const CONFIGURATION_SCORE: u32 = 95;
+/// Any other XML document is parsed generically.
+const GENERIC_XML_SCORE: u32 = 20;
+const INDENT: usize = 4;
+
+/// Elements that wrap the configuration in `display xml` output and NETCONF
+/// replies. They are kept on output but are not segments.
+const ENVELOPES: &[&str] = &["rpc-reply", "data", "configuration"];
+/// The child element that names an entry, as in `<interface><name>ge-0/0/0`.
+const KEY_ELEMENT: &str = "name";
+/// Configuration annotations, the XML form of `/* ... */`.
+const COMMENT_ELEMENT: &str = "junos:comment";
+
+pub(super) fn score(text: &str) -> u32 {
+    if !text.trim_start().starts_with('<') {
+        return 0;
+    }
+    if text.contains("<configuration") {
+        CONFIGURATION_SCORE
+    } else {
+        GENERIC_XML_SCORE
+    }
+}
+
+/// Elements become nodes named like their brace equivalent: `<interface>`
+/// with a `<name>ge-0/0/0</name>` child matches `interface ge-0/0/0`, and a
+/// leaf such as `<mtu>9192</mtu>` matches `mtu 9192`. Attributes are hidden
+/// `name=value` children of their element; those of `<configuration>` sit at
+/// the top level. The `<rpc-reply>`/`<configuration>` envelope is restored
+/// around every selection so the output stays well formed. Text that is not
+/// well-formed XML yields an empty configuration.
+pub(super) fn parse(text: &str) -> ParsedConfig {
+    let mut parsed = ParsedConfig::default();
+    let Some(root) = document(text) else {
+        return parsed;
+    };
+
+    let mut envelope = Vec::new();
+    let mut current = &root;
+    let roots = loop {
+        if !ENVELOPES.contains(&current.local_name()) {
+            break std::slice::from_ref(current);
+        }
+        let indent = indent(envelope.len());
+        envelope.push((
+            format!("{indent}<{}>", current.tag),
+            format!("{indent}</{}>", current.name),
+        ));
+        if current.local_name() == "configuration" {
+            push_attributes(&mut parsed, current, None);
+            break current.children.as_slice();
+        }
+        let inner = current
+            .elements()
+            .find(|child| matches!(child.local_name(), "data" | "configuration"));
+        match inner {
+            Some(inner) => current = inner,
+            None => break current.children.as_slice(),
+        }
+    };
+
+    let depth = envelope.len();
+    for node in roots {
+        push_node(&mut parsed, node, None, depth);
+    }
+    parsed.layout = Layout::Xml { envelope };
+    parsed
+}
+
+/// An element read from the document, with its start tag kept as written.
+#[derive(Default)]
+struct Element {
+    /// Everything between `<` and `>` of the start tag.
+    tag: String,
+    /// The qualified name, such as `interface` or `junos:comment`.
+    name: String,
+    attributes: Vec<(String, String)>,
+    children: Vec<Element>,
+    /// Text content as written, with entity references intact.
+    raw_text: String,
+    /// Text content with entity references resolved.
+    text: String,
+    /// `<!-- ... -->` comments are stored as elements with this set.
+    comment: bool,
+}
+
+impl Element {
+    fn start(start: &BytesStart<'_>) -> Option<Self> {
+        let attributes = start
+            .attributes()
+            .map(|attribute| {
+                let attribute = attribute.ok()?;
+                let key = String::from_utf8(attribute.key.as_ref().to_vec()).ok()?;
+                let value = attribute.unescape_value().ok()?.into_owned();
+                Some((key, value))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(Self {
+            tag: String::from_utf8(start.to_vec()).ok()?,
+            name: String::from_utf8(start.name().as_ref().to_vec()).ok()?,
+            attributes,
+            ..Self::default()
+        })
+    }
+
+    fn local_name(&self) -> &str {
+        self.name
+            .rsplit_once(':')
+            .map_or(self.name.as_str(), |(_, local)| local)
+    }
+
+    fn elements(&self) -> impl Iterator<Item = &Self> {
+        self.children.iter().filter(|child| !child.comment)
+    }
+
+    /// `inactive="inactive"` and `protect="protect"` carry the same state as
+    /// the brace form's `inactive:` and `protect:` prefixes.
+    fn flags(&self) -> NodeFlags {
+        self.attributes
+            .iter()
+            .filter_map(|(name, value)| {
+                let prefix = format!("{name}:");
+                (value == name).then(|| NodeFlags::strip_prefixes(&prefix).0)
+            })
+            .fold(NodeFlags::default(), NodeFlags::union)
+    }
+
+    /// The `<name>` child that identifies this entry, if it has one.
+    fn key(&self) -> Option<&Self> {
+        self.elements().find(|child| {
+            child.name == KEY_ELEMENT
+                && child.elements().next().is_none()
+                && !child.text.trim().is_empty()
+        })
+    }
+}
+
+/// Read the document element, or `None` when the text is not well-formed.
+fn document(text: &str) -> Option<Element> {
+    let mut reader = Reader::from_str(text);
+    let mut stack: Vec<Element> = Vec::new();
+    let mut root = None;
+
+    loop {
+        match reader.read_event().ok()? {
+            Event::Start(start) => stack.push(Element::start(&start)?),
+            Event::Empty(start) => {
+                let element = Element::start(&start)?;
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(element),
+                    None => root = Some(element),
+                }
+            }
+            Event::End(_) => {
+                let element = stack.pop()?;
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(element),
+                    None => root = Some(element),
+                }
+            }
+            Event::Text(text) => {
+                if let Some(current) = stack.last_mut() {
+                    let text = text.decode().ok()?;
+                    current.raw_text.push_str(&text);
+                    current.text.push_str(&text);
+                }
+            }
+            Event::CData(data) => {
+                if let Some(current) = stack.last_mut() {
+                    let data = data.decode().ok()?;
+                    current.raw_text.push_str(&escape(data.as_ref()));
+                    current.text.push_str(&data);
+                }
+            }
+            Event::GeneralRef(reference) => {
+                if let Some(current) = stack.last_mut() {
+                    let name = reference.decode().ok()?;
+                    let resolved = match reference.resolve_char_ref().ok()? {
+                        Some(ch) => ch.to_string(),
+                        None => resolve_predefined_entity(&name)?.to_string(),
+                    };
+                    current.raw_text.push_str(&format!("&{name};"));
+                    current.text.push_str(&resolved);
+                }
+            }
+            Event::Comment(comment) => {
+                if let Some(current) = stack.last_mut() {
+                    let raw = comment.decode().ok()?.into_owned();
+                    current.children.push(Element {
+                        text: raw.trim().to_string(),
+                        raw_text: raw,
+                        comment: true,
+                        ..Element::default()
+                    });
+                }
+            }
+            Event::Eof => break,
+            Event::Decl(_) | Event::PI(_) | Event::DocType(_) => {}
+        }
+    }
+
+    if stack.is_empty() { root } else { None }
+}
+
+fn push_node(parsed: &mut ParsedConfig, node: &Element, parent: Option<usize>, depth: usize) {
+    let indent = indent(depth);
+
+    if node.comment {
+        parsed.push_line(
+            format!("{indent}<!--{}-->", node.raw_text),
+            Some(node.text.clone()),
+            LineKind::Comment,
+            parent,
+        );
+        return;
+    }
+    if node.name == COMMENT_ELEMENT {
+        let text = node.text.trim();
+        let text = text.strip_prefix("/*").unwrap_or(text);
+        let text = text.strip_suffix("*/").unwrap_or(text);
+        parsed.push_line(
+            format!(
+                "{indent}<{}>{}</{}>",
+                node.tag,
+                node.raw_text.trim(),
+                node.name
+            ),
+            Some(text.trim().to_string()),
+            LineKind::Comment,
+            parent,
+        );
+        return;
+    }
+
+    if node.elements().next().is_none() {
+        let value = node.text.trim();
+        let (raw, match_text) = if value.is_empty() {
+            (
+                format!("{indent}<{}/>", tag_name(node)),
+                node.local_name().to_string(),
+            )
+        } else {
+            (
+                format!(
+                    "{indent}<{}>{}</{}>",
+                    node.tag,
+                    node.raw_text.trim(),
+                    node.name
+                ),
+                format!("{} {value}", node.local_name()),
+            )
+        };
+        let idx = parsed.push_line(raw, Some(match_text), LineKind::Command, parent);
+        parsed.lines[idx].flags = node.flags();
+        push_attributes(parsed, node, Some(idx));
+        return;
+    }
+
+    let key = node.key();
+    let mut raw = format!("{indent}<{}>", node.tag);
+    let mut match_text = node.local_name().to_string();
+    if let Some(key) = key {
+        raw.push_str(&format!(
+            "\n{}<{}>{}</{}>",
+            self::indent(depth + 1),
+            key.tag,
+            key.raw_text.trim(),
+            key.name
+        ));
+        match_text.push(' ');
+        match_text.push_str(key.text.trim());
+    }
+    let idx = parsed.push_line(raw, Some(match_text), LineKind::Command, parent);
+    parsed.lines[idx].flags = node.flags();
+    push_attributes(parsed, node, Some(idx));
+
+    for child in &node.children {
+        if key.is_some_and(|key| std::ptr::eq(key, child)) {
+            continue;
+        }
+        push_node(parsed, child, Some(idx), depth + 1);
+    }
+    parsed.push_line(
+        format!("{indent}</{}>", node.name),
+        None,
+        LineKind::Closing,
+        Some(idx),
+    );
+}
+
+/// Expose attributes other than namespace declarations as `name=value`.
+fn push_attributes(parsed: &mut ParsedConfig, node: &Element, parent: Option<usize>) {
+    for (name, value) in &node.attributes {
+        if name == "xmlns" || name.starts_with("xmlns:") {
+            continue;
+        }
+        parsed.push_line(
+            String::new(),
+            Some(format!("{name}={value}")),
+            LineKind::Attribute,
+            parent,
+        );
+    }
+}
+
+/// The start tag of an element that had `<x></x>` written out, collapsed to
+/// its self-closing form.
+fn tag_name(node: &Element) -> &str {
+    node.tag.strip_suffix('/').unwrap_or(&node.tag).trim_end()
+}
+
+fn indent(depth: usize) -> String {
+    " ".repeat(depth * INDENT)
+}
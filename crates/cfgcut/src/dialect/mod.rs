@@ -1,66 +1,397 @@
+use std::fmt;
+use std::str::FromStr;
+
 use serde::Serialize;
 
+mod aoscx;
+mod asa;
 mod brace;
 mod eos;
+mod f5;
+mod fortios;
 mod indent;
 mod ios;
+mod iosxr;
+pub(crate) mod json;
 mod junos;
 mod junos_set;
+mod junos_xml;
+mod nokia_sros;
+mod nokia_sros_md;
 mod nxos;
+mod openconfig;
+mod panos;
+mod panos_set;
+mod procurve;
+mod routeros;
 pub mod shared;
+mod sonic;
+mod vrp;
+mod vyos;
+mod vyos_set;
+mod yaml;
 
-pub use shared::{LineKind, ParsedConfig};
+pub use shared::{Layout, LineKind, NodeFlags, ParsedConfig};
 
+/// Configuration dialects understood by the parser.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[non_exhaustive]
 pub enum DialectKind {
+    /// Cisco IOS and IOS-XE running configurations.
     CiscoIos,
+    /// Cisco NX-OS running configurations.
     CiscoNxos,
+    /// Cisco IOS-XR running configurations.
+    CiscoIosXr,
+    /// Arista EOS running configurations.
     AristaEos,
+    /// Juniper Junos hierarchical (brace) configurations.
     JuniperJunos,
+    /// Juniper Junos `display set` output.
     JuniperJunosSet,
+    /// Palo Alto PAN-OS hierarchical (brace) configurations.
+    PaloAltoPanos,
+    /// Palo Alto PAN-OS `set` output.
+    PaloAltoPanosSet,
+    /// Fortinet FortiOS (FortiGate) configurations.
+    FortinetFortios,
+    /// Nokia SR OS classic CLI configurations.
+    NokiaSros,
+    /// Nokia SR OS MD-CLI configurations.
+    NokiaSrosMd,
+    /// Huawei VRP and H3C Comware configurations.
+    HuaweiVrp,
+    /// MikroTik RouterOS `/export` output.
+    MikrotikRouteros,
+    /// Cisco ASA and Firepower Threat Defense running configurations.
+    CiscoAsa,
+    /// F5 BIG-IP `bigip.conf` (tmsh) configurations.
+    F5BigIp,
+    /// VyOS and Ubiquiti EdgeOS hierarchical (brace) configurations.
+    Vyos,
+    /// VyOS and Ubiquiti EdgeOS `show configuration commands` output.
+    VyosSet,
+    /// SONiC `config_db.json` documents.
+    SonicConfigDb,
+    /// OpenConfig (RFC 7951) JSON, such as saved gNMI snapshots, and other
+    /// JSON documents.
+    OpenconfigJson,
+    /// YAML configurations such as Cumulus NVUE `startup.yaml` and netplan.
+    Yaml,
+    /// Juniper Junos `display xml` output and NETCONF `<configuration>`
+    /// replies.
+    JuniperJunosXml,
+    /// Aruba AOS-CX running configurations.
+    ArubaAoscx,
+    /// HPE ProCurve and ArubaOS-Switch configurations.
+    HpeProcurve,
+}
+
+impl DialectKind {
+    /// Returns the canonical name accepted by `--dialect`.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::CiscoIos => "cisco-ios",
+            Self::CiscoNxos => "cisco-nxos",
+            Self::CiscoIosXr => "cisco-iosxr",
+            Self::AristaEos => "arista-eos",
+            Self::JuniperJunos => "juniper-junos",
+            Self::JuniperJunosSet => "juniper-junos-set",
+            Self::PaloAltoPanos => "paloalto-panos",
+            Self::PaloAltoPanosSet => "paloalto-panos-set",
+            Self::FortinetFortios => "fortinet-fortios",
+            Self::NokiaSros => "nokia-sros",
+            Self::NokiaSrosMd => "nokia-sros-md",
+            Self::HuaweiVrp => "huawei-vrp",
+            Self::MikrotikRouteros => "mikrotik-routeros",
+            Self::CiscoAsa => "cisco-asa",
+            Self::F5BigIp => "f5-bigip",
+            Self::Vyos => "vyos",
+            Self::VyosSet => "vyos-set",
+            Self::SonicConfigDb => "sonic-config-db",
+            Self::OpenconfigJson => "openconfig-json",
+            Self::Yaml => "yaml",
+            Self::JuniperJunosXml => "juniper-junos-xml",
+            Self::ArubaAoscx => "aruba-aoscx",
+            Self::HpeProcurve => "hpe-procurve",
+        }
+    }
+
+    /// Returns every dialect in detection priority order.
+    pub fn all() -> impl Iterator<Item = Self> {
+        DIALECTS.iter().map(|descriptor| descriptor.kind)
+    }
+}
+
+impl fmt::Display for DialectKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Error returned when a dialect name is not recognised.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownDialect {
+    name: String,
+}
+
+impl UnknownDialect {
+    /// The dialect name supplied by the caller.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl fmt::Display for UnknownDialect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let known = DialectKind::all()
+            .map(DialectKind::as_str)
+            .collect::<Vec<_>>()
+            .join(", ");
+        write!(
+            f,
+            "unknown dialect '{}' (expected one of: {known})",
+            self.name
+        )
+    }
+}
+
+impl std::error::Error for UnknownDialect {}
+
+impl FromStr for DialectKind {
+    type Err = UnknownDialect;
+
+    fn from_str(raw: &str) -> Result<Self, Self::Err> {
+        let name = raw.trim().to_ascii_lowercase().replace('_', "-");
+        let kind = match name.as_str() {
+            "cisco-ios" | "ios" | "ios-xe" => Self::CiscoIos,
+            "cisco-nxos" | "nxos" | "nx-os" => Self::CiscoNxos,
+            "cisco-iosxr" | "cisco-ios-xr" | "iosxr" | "ios-xr" => Self::CiscoIosXr,
+            "arista-eos" | "eos" => Self::AristaEos,
+            "juniper-junos" | "junos" => Self::JuniperJunos,
+            "juniper-junos-set" | "junos-set" => Self::JuniperJunosSet,
+            "paloalto-panos" | "panos" | "pan-os" => Self::PaloAltoPanos,
+            "paloalto-panos-set" | "panos-set" | "pan-os-set" => Self::PaloAltoPanosSet,
+            "fortinet-fortios" | "fortios" | "fortigate" => Self::FortinetFortios,
+            "nokia-sros" | "sros" | "timos" => Self::NokiaSros,
+            "nokia-sros-md" | "sros-md" | "md-cli" => Self::NokiaSrosMd,
+            "huawei-vrp" | "vrp" | "h3c-comware" | "comware" => Self::HuaweiVrp,
+            "mikrotik-routeros" | "routeros" | "mikrotik" => Self::MikrotikRouteros,
+            "cisco-asa" | "asa" | "cisco-ftd" | "ftd" => Self::CiscoAsa,
+            "f5-bigip" | "f5" | "bigip" | "big-ip" | "tmsh" => Self::F5BigIp,
+            "vyos" | "edgeos" | "vyatta" => Self::Vyos,
+            "vyos-set" | "edgeos-set" | "vyatta-set" => Self::VyosSet,
+            "sonic-config-db" | "sonic" | "config-db" => Self::SonicConfigDb,
+            "openconfig-json" | "openconfig" | "json" => Self::OpenconfigJson,
+            "yaml" | "yml" | "nvue" | "cumulus-nvue" | "netplan" => Self::Yaml,
+            "juniper-junos-xml" | "junos-xml" | "netconf" | "xml" => Self::JuniperJunosXml,
+            "aruba-aoscx" | "aoscx" | "aos-cx" | "arubaos-cx" => Self::ArubaAoscx,
+            "hpe-procurve" | "procurve" | "arubaos-switch" | "aos-s" | "provision" => {
+                Self::HpeProcurve
+            }
+            _ => {
+                return Err(UnknownDialect {
+                    name: raw.to_string(),
+                });
+            }
+        };
+        Ok(kind)
+    }
 }
 
 struct DialectDescriptor {
     kind: DialectKind,
-    detect: fn(&str) -> bool,
+    score: fn(&str) -> u32,
     parse: fn(&str) -> ParsedConfig,
 }
 
 const DIALECTS: &[DialectDescriptor] = &[
+    DialectDescriptor {
+        kind: DialectKind::SonicConfigDb,
+        score: sonic::score,
+        parse: sonic::parse,
+    },
+    DialectDescriptor {
+        kind: DialectKind::OpenconfigJson,
+        score: openconfig::score,
+        parse: openconfig::parse,
+    },
+    DialectDescriptor {
+        kind: DialectKind::Yaml,
+        score: yaml::score,
+        parse: yaml::parse,
+    },
+    DialectDescriptor {
+        kind: DialectKind::JuniperJunosXml,
+        score: junos_xml::score,
+        parse: junos_xml::parse,
+    },
+    DialectDescriptor {
+        kind: DialectKind::MikrotikRouteros,
+        score: routeros::score,
+        parse: routeros::parse,
+    },
+    DialectDescriptor {
+        kind: DialectKind::HuaweiVrp,
+        score: vrp::score,
+        parse: vrp::parse,
+    },
+    DialectDescriptor {
+        kind: DialectKind::NokiaSrosMd,
+        score: nokia_sros_md::score,
+        parse: nokia_sros_md::parse,
+    },
+    DialectDescriptor {
+        kind: DialectKind::NokiaSros,
+        score: nokia_sros::score,
+        parse: nokia_sros::parse,
+    },
+    DialectDescriptor {
+        kind: DialectKind::FortinetFortios,
+        score: fortios::score,
+        parse: fortios::parse,
+    },
+    DialectDescriptor {
+        kind: DialectKind::F5BigIp,
+        score: f5::score,
+        parse: f5::parse,
+    },
+    DialectDescriptor {
+        kind: DialectKind::PaloAltoPanosSet,
+        score: panos_set::score,
+        parse: panos_set::parse,
+    },
+    DialectDescriptor {
+        kind: DialectKind::PaloAltoPanos,
+        score: panos::score,
+        parse: panos::parse,
+    },
+    DialectDescriptor {
+        kind: DialectKind::VyosSet,
+        score: vyos_set::score,
+        parse: vyos_set::parse,
+    },
+    DialectDescriptor {
+        kind: DialectKind::Vyos,
+        score: vyos::score,
+        parse: vyos::parse,
+    },
     DialectDescriptor {
         kind: DialectKind::JuniperJunosSet,
-        detect: junos_set::detect,
+        score: junos_set::score,
         parse: junos_set::parse,
     },
     DialectDescriptor {
         kind: DialectKind::JuniperJunos,
-        detect: junos::detect,
+        score: junos::score,
         parse: junos::parse,
     },
+    DialectDescriptor {
+        kind: DialectKind::HpeProcurve,
+        score: procurve::score,
+        parse: procurve::parse,
+    },
+    DialectDescriptor {
+        kind: DialectKind::ArubaAoscx,
+        score: aoscx::score,
+        parse: aoscx::parse,
+    },
     DialectDescriptor {
         kind: DialectKind::AristaEos,
-        detect: eos::detect,
+        score: eos::score,
         parse: eos::parse,
     },
+    DialectDescriptor {
+        kind: DialectKind::CiscoAsa,
+        score: asa::score,
+        parse: asa::parse,
+    },
+    DialectDescriptor {
+        kind: DialectKind::CiscoIosXr,
+        score: iosxr::score,
+        parse: iosxr::parse,
+    },
     DialectDescriptor {
         kind: DialectKind::CiscoNxos,
-        detect: nxos::detect,
+        score: nxos::score,
         parse: nxos::parse,
     },
     DialectDescriptor {
         kind: DialectKind::CiscoIos,
-        detect: ios::detect,
+        score: ios::score,
         parse: ios::parse,
     },
 ];
 
-pub fn parse_with_detect(text: &str) -> (DialectKind, ParsedConfig) {
+/// Outcome of scoring every dialect against a configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Detection {
+    /// The dialect selected for parsing.
+    pub kind: DialectKind,
+    /// The confidence score of the selected dialect.
+    pub score: u32,
+    /// The best-scoring alternative, if any dialect other than the winner scored.
+    pub runner_up: Option<(DialectKind, u32)>,
+}
+
+impl Detection {
+    /// Whether the runner-up scored close enough that the choice is uncertain.
+    #[must_use]
+    pub const fn is_ambiguous(&self) -> bool {
+        match self.runner_up {
+            Some((_, runner_score)) => runner_score > 0 && self.score < runner_score * 2,
+            None => false,
+        }
+    }
+}
+
+/// Score every dialect and return the best candidate.
+///
+/// Ties are resolved in favour of the dialect listed first in the detection
+/// table. When nothing scores, the configuration is treated as Cisco IOS.
+pub fn detect(text: &str) -> Detection {
+    let mut best: Option<(DialectKind, u32)> = None;
+    let mut runner_up: Option<(DialectKind, u32)> = None;
+
     for descriptor in DIALECTS {
-        if (descriptor.detect)(text) {
-            return (descriptor.kind, (descriptor.parse)(text));
+        let score = (descriptor.score)(text);
+        if score == 0 {
+            continue;
+        }
+        match best {
+            Some((_, best_score)) if score <= best_score => {
+                if runner_up.is_none_or(|(_, runner_score)| score > runner_score) {
+                    runner_up = Some((descriptor.kind, score));
+                }
+            }
+            _ => {
+                runner_up = best;
+                best = Some((descriptor.kind, score));
+            }
         }
     }
-    (DialectKind::CiscoIos, ios::parse(text))
+
+    let (kind, score) = best.unwrap_or((DialectKind::CiscoIos, 0));
+    Detection {
+        kind,
+        score,
+        runner_up,
+    }
+}
+
+/// Parse `text` using the given dialect, bypassing detection.
+pub fn parse_as(kind: DialectKind, text: &str) -> ParsedConfig {
+    DIALECTS
+        .iter()
+        .find(|descriptor| descriptor.kind == kind)
+        .map_or_else(|| ios::parse(text), |descriptor| (descriptor.parse)(text))
+}
+
+pub fn parse_with_detect(text: &str) -> (DialectKind, ParsedConfig) {
+    let detection = detect(text);
+    (detection.kind, parse_as(detection.kind, text))
 }
 
 #[cfg(test)]
@@ -90,23 +421,56 @@ mod tests {
         );
     }
 
+    #[test]
+    fn locate_lines_skips_synthesised_entries_and_keeps_source_order() {
+        let text = "set interfaces ge-0/0/0 unit 0\nset interfaces ge-0/0/1 unit 0\n";
+        let mut parsed = parse_as(DialectKind::JuniperJunosSet, text);
+        assert!(parsed.lines.iter().all(|line| line.line.is_none()));
+        parsed.locate_lines(text);
+        let lines = parsed
+            .lines
+            .iter()
+            .map(|line| (line.raw.as_str(), line.line))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            vec![
+                ("set interfaces", None),
+                ("set interfaces ge-0/0/0", None),
+                ("set interfaces ge-0/0/0 unit 0", Some(1)),
+                ("set interfaces ge-0/0/1", None),
+                ("set interfaces ge-0/0/1 unit 0", Some(2)),
+            ]
+        );
+
+        let text = "interface Gi1\n shutdown\ninterface Gi2\n shutdown\n";
+        let mut parsed = parse_as(DialectKind::CiscoIos, text);
+        parsed.locate_lines(text);
+        let lines = parsed
+            .lines
+            .iter()
+            .map(|line| line.line)
+            .collect::<Vec<_>>();
+        assert_eq!(lines, vec![Some(1), Some(2), Some(3), Some(4)]);
+    }
+
     #[test]
     fn eos_detect_recognises_platform_markers() {
         let text = "! device: Arista cEOSLAB\ninterface Ethernet1\n";
-        assert!(eos::detect(text));
-        assert!(!eos::detect("interface Ethernet1\n"));
+        assert!(eos::score(text) > 0);
+        assert_eq!(eos::score("interface Ethernet1\n"), 0);
     }
 
     #[test]
     fn nxos_detects_feature_lines() {
         let text = "feature interface-vlan\ninterface Ethernet1/1\n";
-        assert!(nxos::detect(text));
+        assert!(nxos::score(text) > 0);
     }
 
     #[test]
     fn junos_set_detects_set_syntax() {
         let text = "set system host-name vsrx\nset interfaces ge-0/0/0 unit 0 family inet address 192.0.2.1/24\n";
-        assert!(junos_set::detect(text));
+        assert!(junos_set::score(text) > 0);
     }
 
     #[test]
@@ -133,4 +497,898 @@ mod tests {
         assert_eq!(parsed.lines[closing_idx].raw.trim(), "}");
         assert_eq!(parsed.lines[closing_idx].parent, Some(services_idx));
     }
+
+    #[test]
+    fn banner_feature_text_does_not_select_nxos() {
+        let text = "banner motd ^C\nfeature freeze in effect\n^C\ninterface GigabitEthernet1\n ip address dhcp\n!\n";
+        let detection = detect(text);
+        assert_eq!(detection.kind, DialectKind::CiscoIos);
+    }
+
+    #[test]
+    fn platform_names_in_descriptions_do_not_select_a_dialect() {
+        for text in [
+            "interface GigabitEthernet1\n description link to Nexus-01\n!\n",
+            "interface GigabitEthernet1\n description NX-OS uplink\n!\n",
+            "interface GigabitEthernet1\n description to Arista core\n!\n",
+            "interface GigabitEthernet1\n description vEOS lab peer\n!\n",
+        ] {
+            let detection = detect(text);
+            assert_eq!(detection.kind, DialectKind::CiscoIos, "{text}");
+            assert!(!detection.is_ambiguous(), "{text}");
+        }
+        assert_eq!(
+            detect(
+                "!Command: show running-config\n!Running on Nexus 9000\ninterface Ethernet1/1\n"
+            )
+            .kind,
+            DialectKind::CiscoNxos
+        );
+    }
+
+    #[test]
+    fn eos_idioms_outrank_generic_ios() {
+        let text = "service routing protocols model multi-agent\n!\ninterface Ethernet1\n description uplink\n";
+        let detection = detect(text);
+        assert_eq!(detection.kind, DialectKind::AristaEos);
+        assert_eq!(
+            detection.runner_up.map(|(kind, _)| kind),
+            Some(DialectKind::CiscoIos)
+        );
+        assert!(!detection.is_ambiguous());
+    }
+
+    #[test]
+    fn close_scores_are_reported_as_ambiguous() {
+        let detection = Detection {
+            kind: DialectKind::CiscoNxos,
+            score: 20,
+            runner_up: Some((DialectKind::AristaEos, 15)),
+        };
+        assert!(detection.is_ambiguous());
+    }
+
+    #[test]
+    fn dialect_names_round_trip() {
+        for kind in DialectKind::all() {
+            assert_eq!(kind.as_str().parse::<DialectKind>(), Ok(kind));
+        }
+        assert_eq!(
+            "junos_set".parse::<DialectKind>(),
+            Ok(DialectKind::JuniperJunosSet)
+        );
+        assert!("vyos-classic".parse::<DialectKind>().is_err());
+    }
+
+    #[test]
+    fn iosxr_detects_prompt_and_header_markers() {
+        assert!(iosxr::score("RP/0/RSP0/CPU0:edge1#show running-config\nhostname edge1\n") > 0);
+        assert!(iosxr::score("!! IOS XR Configuration 7.3.2\nhostname edge1\n") > 0);
+        assert_eq!(iosxr::score("hostname edge1\ninterface Loopback0\n"), 0);
+        let detection = detect("!! IOS XR Configuration 7.3.2\n!\ninterface Loopback0\n");
+        assert_eq!(detection.kind, DialectKind::CiscoIosXr);
+    }
+
+    #[test]
+    fn iosxr_route_policy_closes_with_keyword() {
+        let text = "route-policy PASS\n  if destination in PFX then\n    pass\n  else\n    drop\n  endif\nend-policy\n!\nprefix-set PFX\n  10.0.0.0/8 le 24,\n  192.0.2.0/24\nend-set\n";
+        let parsed = iosxr::parse(text);
+
+        let policy = 0;
+        assert_eq!(
+            parsed.lines[policy].match_text.as_deref(),
+            Some("route-policy PASS")
+        );
+        let end_policy = parsed.children[policy]
+            .iter()
+            .copied()
+            .find(|&idx| matches!(parsed.lines[idx].kind, LineKind::Closing))
+            .expect("end-policy closing child present");
+        assert_eq!(parsed.lines[end_policy].raw, "end-policy");
+
+        let if_idx = parsed.children[policy][0];
+        assert_eq!(
+            parsed.lines[if_idx].match_text.as_deref(),
+            Some("if destination in PFX then")
+        );
+        let else_idx = parsed
+            .lines
+            .iter()
+            .position(|line| line.match_text.as_deref() == Some("else"))
+            .expect("else branch present");
+        assert_eq!(parsed.lines[else_idx].parent, Some(if_idx));
+        let endif = parsed
+            .lines
+            .iter()
+            .position(|line| line.raw.trim() == "endif")
+            .expect("endif present");
+        assert!(matches!(parsed.lines[endif].kind, LineKind::Closing));
+        assert_eq!(parsed.lines[endif].parent, Some(if_idx));
+
+        let entry = parsed
+            .lines
+            .iter()
+            .position(|line| line.raw.trim() == "10.0.0.0/8 le 24,")
+            .expect("prefix entry present");
+        assert_eq!(
+            parsed.lines[entry].match_text.as_deref(),
+            Some("10.0.0.0/8 le 24")
+        );
+        assert!(parsed.lines[entry].parent.is_some());
+    }
+
+    #[test]
+    fn iosxr_bang_lines_close_indented_blocks() {
+        let text =
+            "router bgp 65000\n neighbor 192.0.2.1\n  remote-as 65001\n !\n!\nhostname edge1\n!\n";
+        let parsed = iosxr::parse(text);
+        let closings = parsed
+            .lines
+            .iter()
+            .filter(|line| matches!(line.kind, LineKind::Closing))
+            .map(|line| line.parent)
+            .collect::<Vec<_>>();
+        assert_eq!(closings, vec![Some(1), Some(0)]);
+        let separator = parsed.lines.last().expect("trailing separator");
+        assert!(matches!(separator.kind, LineKind::Comment));
+        assert_eq!(separator.parent, None);
+    }
+
+    #[test]
+    fn panos_set_keeps_quoted_names_and_lists_together() {
+        let text = "set rulebase security rules \"Allow Web\" from trust\nset rulebase security rules \"Allow Web\" application [ web-browsing ssl ]\n";
+        let parsed = panos_set::parse(text);
+        let texts = parsed
+            .lines
+            .iter()
+            .filter_map(|line| line.match_text.as_deref())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            texts,
+            vec![
+                "rulebase",
+                "security",
+                "rules",
+                "Allow Web",
+                "from trust",
+                "application [ web-browsing ssl ]",
+            ]
+        );
+        let rule = texts.iter().position(|text| *text == "Allow Web").unwrap();
+        assert_eq!(
+            parsed.lines[rule].raw,
+            "set rulebase security rules \"Allow Web\""
+        );
+    }
+
+    #[test]
+    fn panos_brace_ignores_braces_inside_quotes() {
+        let text = "rulebase {\n  security {\n    rules {\n      \"Allow {web}\" {\n        from trust;\n      }\n    }\n  }\n}\n";
+        let parsed = panos::parse(text);
+        let rule = parsed
+            .lines
+            .iter()
+            .position(|line| line.match_text.as_deref() == Some("Allow {web}"))
+            .expect("quoted rule name present");
+        let from = parsed
+            .lines
+            .iter()
+            .position(|line| line.match_text.as_deref() == Some("from trust"))
+            .expect("leaf present");
+        assert_eq!(parsed.lines[from].parent, Some(rule));
+        let closings = parsed
+            .lines
+            .iter()
+            .filter(|line| matches!(line.kind, LineKind::Closing))
+            .count();
+        assert_eq!(closings, 4);
+    }
+
+    #[test]
+    fn panos_detection_prefers_panos_roots() {
+        let set =
+            "set deviceconfig system hostname fw01\nset zone trust network layer3 ethernet1/2\n";
+        assert_eq!(detect(set).kind, DialectKind::PaloAltoPanosSet);
+        assert!(!detect(set).is_ambiguous());
+
+        let junos_set = "set system host-name vsrx\nset interfaces ge-0/0/0 unit 0\n";
+        assert_eq!(detect(junos_set).kind, DialectKind::JuniperJunosSet);
+
+        let brace = "deviceconfig {\n  system {\n    hostname fw01;\n  }\n}\n";
+        assert_eq!(detect(brace).kind, DialectKind::PaloAltoPanos);
+        assert!(!detect(brace).is_ambiguous());
+
+        let junos = "system {\n    host-name vsrx;\n}\n";
+        assert_eq!(detect(junos).kind, DialectKind::JuniperJunos);
+    }
+
+    #[test]
+    fn fortios_nests_by_keywords_and_closes_blocks() {
+        let text = "config system interface\n    edit \"port1\"\n        set description \"WAN uplink\"\n        set ip 192.0.2.1 255.255.255.0\n    next\nend\n";
+        let parsed = fortios::parse(text);
+        assert_eq!(parsed.lines[1].parent, Some(0));
+        assert_eq!(parsed.lines[1].match_text.as_deref(), Some("edit port1"));
+        assert_eq!(parsed.lines[2].parent, Some(1));
+        assert_eq!(
+            parsed.lines[2].match_text.as_deref(),
+            Some("set description WAN uplink")
+        );
+        assert!(matches!(parsed.lines[4].kind, LineKind::Closing));
+        assert_eq!(parsed.lines[4].parent, Some(1));
+        assert!(matches!(parsed.lines[5].kind, LineKind::Closing));
+        assert_eq!(parsed.lines[5].parent, Some(0));
+    }
+
+    #[test]
+    fn fortios_detection_beats_ios_fallback() {
+        let text = "#config-version=FGT60F-7.2.5-FW-build1517-230606:opmode=0\nconfig system global\n    set hostname \"FGT\"\nend\n";
+        assert_eq!(detect(text).kind, DialectKind::FortinetFortios);
+        let bare = "config system interface\n    edit \"port1\"\n    next\nend\n";
+        assert_eq!(detect(bare).kind, DialectKind::FortinetFortios);
+    }
+
+    #[test]
+    fn sros_classic_exit_lines_close_blocks() {
+        let text = "configure\n    system\n        name \"pe1\"\n        snmp\n        exit\n    exit\n    router Base\n        interface \"to pe2\"\n            address 10.0.0.1/30\n        exit\n    exit\nexit all\n";
+        let parsed = nokia_sros::parse(text);
+        let closing_parents = parsed
+            .lines
+            .iter()
+            .filter(|line| matches!(line.kind, LineKind::Closing))
+            .map(|line| line.parent)
+            .collect::<Vec<_>>();
+        // snmp, system, interface, router, configure
+        assert_eq!(
+            closing_parents,
+            vec![Some(3), Some(1), Some(7), Some(6), Some(0)]
+        );
+        assert_eq!(parsed.lines[2].match_text.as_deref(), Some("name pe1"));
+        assert_eq!(
+            parsed.lines[7].match_text.as_deref(),
+            Some("interface to pe2")
+        );
+        assert_eq!(parsed.lines[8].parent, Some(7));
+    }
+
+    #[test]
+    fn sros_md_quoted_keys_match_without_quotes() {
+        let text = "configure {\n    router \"Base\" {\n        interface \"to pe2\" {\n            port 1/1/1\n        }\n    }\n}\n";
+        let parsed = nokia_sros_md::parse(text);
+        let interface = parsed
+            .lines
+            .iter()
+            .position(|line| line.match_text.as_deref() == Some("interface to pe2"))
+            .expect("quoted key unquoted for matching");
+        assert_eq!(parsed.lines[interface].raw.trim(), "interface \"to pe2\" {");
+        assert_eq!(
+            parsed.lines[interface + 1].match_text.as_deref(),
+            Some("port 1/1/1")
+        );
+    }
+
+    #[test]
+    fn sros_detection_uses_timos_header() {
+        let classic = "# TiMOS-C-20.10.R5 cpm/x86_64 Nokia 7750 SR\nconfigure\n    system\n    exit\nexit all\n";
+        assert_eq!(detect(classic).kind, DialectKind::NokiaSros);
+        let md = "# TiMOS-B-21.10.R1 both/x86_64 Nokia 7750 SR\nconfigure {\n    system {\n        name \"pe1\"\n    }\n}\n";
+        assert_eq!(detect(md).kind, DialectKind::NokiaSrosMd);
+        assert!(!detect(md).is_ambiguous());
+    }
+
+    #[test]
+    fn vrp_separators_close_sections_instead_of_commenting() {
+        let text = "#\nsysname lab-ce1\n#\ninterface GigabitEthernet0/0/1\n description uplink\n undo shutdown\n#\nbgp 65000\n peer 10.0.0.2 as-number 65001\n #\n ipv4-family unicast\n  peer 10.0.0.2 enable\n#\nreturn\n";
+        let parsed = vrp::parse(text);
+        assert!(
+            parsed
+                .lines
+                .iter()
+                .all(|line| !matches!(line.kind, LineKind::Comment))
+        );
+
+        let interface = parsed
+            .lines
+            .iter()
+            .position(|line| line.raw == "interface GigabitEthernet0/0/1")
+            .unwrap();
+        let closing = *parsed.children[interface].last().unwrap();
+        assert!(matches!(parsed.lines[closing].kind, LineKind::Closing));
+        assert_eq!(parsed.lines[closing].raw, "#");
+
+        let family = parsed
+            .lines
+            .iter()
+            .position(|line| line.raw == " ipv4-family unicast")
+            .unwrap();
+        assert_eq!(parsed.lines[family].parent, Some(interface + 4));
+    }
+
+    #[test]
+    fn vrp_quit_closes_the_enclosing_view() {
+        let text = "interface Vlanif10\n ip address 10.0.10.1 255.255.255.0\n quit\nsysname edge\n";
+        let parsed = vrp::parse(text);
+        assert!(matches!(parsed.lines[2].kind, LineKind::Closing));
+        assert_eq!(parsed.lines[2].parent, Some(0));
+        assert_eq!(parsed.lines[3].parent, None);
+    }
+
+    #[test]
+    fn vrp_detected_by_sysname() {
+        let text =
+            "#\nsysname lab-ce1\n#\ninterface GigabitEthernet0/0/1\n undo shutdown\n#\nreturn\n";
+        assert_eq!(detect(text).kind, DialectKind::HuaweiVrp);
+    }
+
+    #[test]
+    fn routeros_joins_continuations_and_nests_under_paths() {
+        let text = "/ip address\nadd address=10.10.0.1/24 comment=\"LAN gateway\" interface=bridge-lan \\\n    network=10.10.0.0\n/system identity\nset name=lab-rb\n";
+        let parsed = routeros::parse(text);
+        assert_eq!(parsed.lines[0].match_text.as_deref(), Some("/ip address"));
+
+        let add = parsed.children[0][0];
+        assert_eq!(
+            parsed.lines[add].raw,
+            "add address=10.10.0.1/24 comment=\"LAN gateway\" interface=bridge-lan \\\n    network=10.10.0.0"
+        );
+        assert_eq!(
+            parsed.lines[add].match_text.as_deref(),
+            Some(
+                "add address=10.10.0.1/24 comment=LAN gateway interface=bridge-lan network=10.10.0.0"
+            )
+        );
+
+        let attributes = parsed.children[add]
+            .iter()
+            .map(|&idx| {
+                assert!(matches!(parsed.lines[idx].kind, LineKind::Attribute));
+                parsed.lines[idx].match_text.as_deref().unwrap()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            attributes,
+            [
+                "address=10.10.0.1/24",
+                "comment=LAN gateway",
+                "interface=bridge-lan",
+                "network=10.10.0.0"
+            ]
+        );
+
+        let identity = parsed
+            .lines
+            .iter()
+            .position(|line| line.raw == "/system identity")
+            .unwrap();
+        assert_eq!(parsed.lines[identity].parent, None);
+        assert_eq!(parsed.lines[identity + 1].parent, Some(identity));
+    }
+
+    #[test]
+    fn routeros_splits_inline_path_commands() {
+        let parsed = routeros::parse("/ip dns set servers=192.0.2.53\n");
+        assert_eq!(parsed.lines[0].raw, "/ip dns");
+        assert_eq!(parsed.lines[1].raw, "set servers=192.0.2.53");
+        assert_eq!(parsed.lines[1].parent, Some(0));
+    }
+
+    #[test]
+    fn routeros_detected_by_export_header() {
+        let text = "# jan/02/2024 09:15:00 by RouterOS 7.12\n# software id = ABCD-1234\n/system identity\nset name=lab-rb\n";
+        assert_eq!(detect(text).kind, DialectKind::MikrotikRouteros);
+        assert!(!detect(text).is_ambiguous());
+        let headerless = "/interface bridge\nadd name=bridge-lan\n";
+        assert_eq!(detect(headerless).kind, DialectKind::MikrotikRouteros);
+    }
+
+    #[test]
+    fn asa_colon_lines_are_comments_and_names_nest() {
+        let text = ": Saved\n:\nASA Version 9.16(4)\n!\nnames\nname 192.0.2.10 web-server\nname 192.0.2.11 mail-server\n!\nobject network web\n host 192.0.2.10\n";
+        let parsed = asa::parse(text);
+        assert!(matches!(parsed.lines[0].kind, LineKind::Comment));
+        assert_eq!(parsed.lines[0].match_text.as_deref(), Some("Saved"));
+
+        let names = parsed
+            .lines
+            .iter()
+            .position(|line| line.raw == "names")
+            .unwrap();
+        assert_eq!(parsed.children[names], [names + 1, names + 2]);
+        let object = parsed
+            .lines
+            .iter()
+            .position(|line| line.raw == "object network web")
+            .unwrap();
+        assert_eq!(parsed.lines[object].parent, None);
+        assert_eq!(parsed.lines[object + 1].parent, Some(object));
+    }
+
+    #[test]
+    fn asa_detected_by_version_banner() {
+        let text = ": Saved\n: Serial Number: JAD000000AA\nASA Version 9.16(4)\n!\nhostname fw1\ninterface GigabitEthernet0/0\n nameif outside\n security-level 0\n";
+        assert_eq!(detect(text).kind, DialectKind::CiscoAsa);
+        assert!(!detect(text).is_ambiguous());
+        let ftd = "NGFW Version 7.2.5\n!\nhostname ftd1\n";
+        assert_eq!(detect(ftd).kind, DialectKind::CiscoAsa);
+    }
+
+    #[test]
+    fn f5_splits_nested_braces_on_one_line() {
+        let text = "ltm virtual /Common/vs_web {\n    profiles { /Common/http { } /Common/tcp { context all } }\n    vlans { /Common/external }\n}\n";
+        let parsed = f5::parse(text);
+        let rendered = parsed
+            .lines
+            .iter()
+            .map(|line| line.raw.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            rendered,
+            [
+                "ltm virtual /Common/vs_web {",
+                "    profiles {",
+                "        /Common/http { }",
+                "        /Common/tcp {",
+                "            context all",
+                "        }",
+                "    }",
+                "    vlans {",
+                "        /Common/external",
+                "    }",
+                "}",
+            ]
+        );
+        assert_eq!(
+            parsed.lines[0].match_text.as_deref(),
+            Some("ltm virtual /Common/vs_web")
+        );
+        assert_eq!(parsed.lines[2].match_text.as_deref(), Some("/Common/http"));
+        assert_eq!(parsed.lines[2].parent, Some(1));
+        assert_eq!(parsed.lines[4].parent, Some(3));
+    }
+
+    #[test]
+    fn f5_keeps_irule_bodies_verbatim() {
+        let text = "ltm rule /Common/redirect {\nwhen HTTP_REQUEST {\n  if { [HTTP::uri] eq \"/\" } {\n    HTTP::redirect \"https://[HTTP::host]/\"\n  }\n}\n}\nltm pool /Common/p { }\n";
+        let parsed = f5::parse(text);
+        assert_eq!(parsed.lines[2].raw, "  if { [HTTP::uri] eq \"/\" } {");
+        assert_eq!(parsed.lines[2].parent, Some(0));
+        assert!(matches!(parsed.lines[6].kind, LineKind::Closing));
+        assert_eq!(parsed.lines[7].raw, "ltm pool /Common/p { }");
+        assert_eq!(parsed.lines[7].parent, None);
+    }
+
+    #[test]
+    fn f5_detected_by_tmsh_header() {
+        let text = "#TMSH-VERSION: 15.1.0\n\nltm pool /Common/pool_web {\n    members {\n        /Common/10.0.0.11:80 {\n            address 10.0.0.11\n        }\n    }\n}\n";
+        assert_eq!(detect(text).kind, DialectKind::F5BigIp);
+        assert!(!detect(text).is_ambiguous());
+        let headerless = "net vlan /Common/external {\n    tag 4094\n}\nsys global-settings {\n    hostname bigip1.example.net\n}\n";
+        assert_eq!(detect(headerless).kind, DialectKind::F5BigIp);
+    }
+
+    #[test]
+    fn junos_module_names_do_not_select_f5() {
+        let fixture = |name: &str| {
+            let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("../../tests/fixtures/juniper_junos")
+                .join(name);
+            std::fs::read_to_string(path).unwrap()
+        };
+        let security = fixture("security.conf");
+        assert_eq!(f5::score(&security), 0);
+        assert_eq!(detect(&security).kind, DialectKind::JuniperJunos);
+        assert!(!detect(&security).is_ambiguous());
+
+        let full = fixture("full_lab.conf") + &security;
+        assert_eq!(detect(&full).kind, DialectKind::JuniperJunos);
+        assert!(!detect(&full).is_ambiguous());
+    }
+
+    #[test]
+    fn vyos_set_keeps_quoted_values_together() {
+        let text = "set interfaces ethernet eth0 description 'WAN uplink'\nset firewall name WAN_IN rule 10 action 'accept'\n";
+        let parsed = vyos_set::parse(text);
+        let segments = parsed
+            .lines
+            .iter()
+            .filter_map(|line| line.match_text.as_deref())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            segments,
+            [
+                "interfaces",
+                "ethernet eth0",
+                "description WAN uplink",
+                "firewall",
+                "name WAN_IN",
+                "rule 10",
+                "action accept",
+            ]
+        );
+        assert_eq!(
+            parsed.lines[2].raw,
+            "set interfaces ethernet eth0 description 'WAN uplink'"
+        );
+    }
+
+    #[test]
+    fn vyos_brace_matches_set_segments() {
+        let text = "interfaces {\n    ethernet eth0 {\n        description 'WAN { uplink }'\n    }\n}\n/* Warning: Do not remove the following line. */\n";
+        let parsed = vyos::parse(text);
+        assert_eq!(
+            parsed.lines[2].match_text.as_deref(),
+            Some("description WAN { uplink }")
+        );
+        assert_eq!(parsed.lines[2].parent, Some(1));
+        let footer = parsed.lines.last().unwrap();
+        assert!(matches!(footer.kind, LineKind::Comment));
+        assert_eq!(
+            footer.match_text.as_deref(),
+            Some("Warning: Do not remove the following line.")
+        );
+    }
+
+    #[test]
+    fn vyos_detection_prefers_vyos_over_junos() {
+        let set =
+            "set interfaces ethernet eth0 address '192.0.2.1/24'\nset system host-name 'vyos1'\n";
+        assert_eq!(detect(set).kind, DialectKind::VyosSet);
+        assert!(!detect(set).is_ambiguous());
+
+        let brace = "interfaces {\n    ethernet eth0 {\n        address 192.0.2.1/24\n    }\n}\nsystem {\n    host-name vyos1\n}\n";
+        assert_eq!(detect(brace).kind, DialectKind::Vyos);
+        assert!(!detect(brace).is_ambiguous());
+
+        let junos = "set interfaces ge-0/0/0 unit 0 family inet address 192.0.2.1/24\nset system host-name edge1\n";
+        assert_eq!(detect(junos).kind, DialectKind::JuniperJunosSet);
+    }
+
+    #[test]
+    fn json_keys_become_nodes_with_value_attributes() {
+        let text = "{\"PORT\": {\"Ethernet0\": {\"mtu\": \"9100\", \"lanes\": [1, 2]}}}";
+        let parsed = json::parse(text);
+        assert_eq!(parsed.layout, Layout::Json { root_array: false });
+        let raws = parsed
+            .lines
+            .iter()
+            .map(|line| line.raw.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            raws,
+            [
+                "  \"PORT\": {",
+                "    \"Ethernet0\": {",
+                "      \"mtu\": \"9100\"",
+                "",
+                "      \"lanes\": [",
+                "        1",
+                "        2",
+                "      ]",
+                "    }",
+                "  }",
+            ]
+        );
+        assert_eq!(parsed.lines[2].match_text.as_deref(), Some("mtu"));
+        assert!(matches!(parsed.lines[3].kind, LineKind::Attribute));
+        assert_eq!(parsed.lines[3].match_text.as_deref(), Some("9100"));
+        assert_eq!(parsed.lines[5].match_text.as_deref(), Some("1"));
+    }
+
+    #[test]
+    fn json_list_entries_are_named_by_their_key() {
+        let text = "{\"interfaces\": {\"interface\": [{\"config\": {}, \"name\": \"Ethernet1\"}, {\"mtu\": 1500}]}}";
+        let parsed = json::parse(text);
+        let entries = &parsed.children[1];
+        assert_eq!(
+            parsed.lines[entries[0]].match_text.as_deref(),
+            Some("Ethernet1")
+        );
+        assert_eq!(parsed.lines[entries[1]].match_text.as_deref(), Some("1"));
+
+        let key = json::with_list_keys(&parsed, &[0, 1, entries[0]]);
+        assert_eq!(parsed.lines[key[3]].raw.trim(), "\"name\": \"Ethernet1\"");
+    }
+
+    #[test]
+    fn json_detection_distinguishes_sonic_and_openconfig() {
+        let sonic =
+            "{\"DEVICE_METADATA\": {\"localhost\": {\"hostname\": \"leaf1\"}}, \"PORT\": {}}";
+        assert_eq!(detect(sonic).kind, DialectKind::SonicConfigDb);
+        assert!(!detect(sonic).is_ambiguous());
+
+        let openconfig = "{\"openconfig-interfaces:interfaces\": {\"interface\": []}}";
+        assert_eq!(detect(openconfig).kind, DialectKind::OpenconfigJson);
+
+        // Plain JSON carries no marker and is left to `--dialect`.
+        let other = "{\"hostname\": \"r1\"}";
+        assert_eq!(detect(other).score, 0);
+        assert_eq!(detect("[{\"openconfig-x:y\": 1}]").score, 0);
+        assert_eq!(detect("{\"PORT\": {} trailing").kind, DialectKind::CiscoIos);
+        assert_eq!(detect("{ not json").kind, DialectKind::CiscoIos);
+    }
+
+    #[test]
+    fn yaml_sequences_keep_their_parent_key() {
+        let text = "network:\n  ethernets:\n    eth0:\n      addresses:\n      - 192.0.2.10/24  # primary\n      routes:\n        - to: default\n          via: 192.0.2.1\n      # resolvers\n      nameservers:\n        addresses: [192.0.2.53]\n";
+        let parsed = yaml::parse(text);
+        let find = |text: &str| {
+            parsed
+                .lines
+                .iter()
+                .position(|line| line.match_text.as_deref() == Some(text))
+                .unwrap()
+        };
+
+        let addresses = find("addresses");
+        let address = find("192.0.2.10/24");
+        assert_eq!(parsed.lines[address].parent, Some(addresses));
+
+        let routes = find("routes");
+        let default = find("to: default");
+        assert_eq!(parsed.lines[default].parent, Some(routes));
+        assert_eq!(parsed.lines[find("via: 192.0.2.1")].parent, Some(default));
+
+        let comment = parsed
+            .lines
+            .iter()
+            .position(|line| line.raw.trim() == "# resolvers")
+            .unwrap();
+        assert!(matches!(parsed.lines[comment].kind, LineKind::Comment));
+        let nameservers = find("nameservers");
+        assert_eq!(parsed.lines[nameservers].parent, Some(find("eth0")));
+        assert_eq!(
+            parsed.lines[find("addresses: [192.0.2.53]")].parent,
+            Some(nameservers)
+        );
+    }
+
+    #[test]
+    fn yaml_block_scalars_are_not_parsed_as_structure() {
+        let text = "system:\n  message:\n    pre-login: |\n      # not a comment\n      key: not a key\n  hostname: leaf1\n";
+        let parsed = yaml::parse(text);
+        assert_eq!(parsed.lines[2].match_text.as_deref(), Some("pre-login"));
+        assert_eq!(parsed.lines[3].parent, Some(2));
+        assert!(matches!(parsed.lines[3].kind, LineKind::Command));
+        assert_eq!(parsed.lines[4].parent, Some(2));
+        assert_eq!(parsed.lines[5].parent, Some(0));
+    }
+
+    #[test]
+    fn yaml_detection() {
+        let nvue = "- header:\n    model: VX\n- set:\n    system:\n      hostname: leaf1\n";
+        assert_eq!(detect(nvue).kind, DialectKind::Yaml);
+        let netplan = "# netplan\nnetwork:\n  version: 2\n";
+        assert_eq!(detect(netplan).kind, DialectKind::Yaml);
+        let ios = "hostname r1\ninterface Gi0/1\n description uplink: core\n";
+        assert_ne!(detect(ios).kind, DialectKind::Yaml);
+    }
+
+    #[test]
+    fn junos_xml_names_entries_by_their_name_child() {
+        let text = "<rpc-reply xmlns:junos=\"urn:junos\">\n<configuration junos:changed-localtime=\"2024-06-10 15:00:00 UTC\">\n<interfaces>\n<interface inactive=\"inactive\">\n<name>ge-0/0/0</name>\n<mtu>9192</mtu>\n<disable></disable>\n</interface>\n</interfaces>\n</configuration>\n</rpc-reply>\n";
+        let parsed = junos_xml::parse(text);
+        let texts = parsed
+            .lines
+            .iter()
+            .map(|line| line.match_text.as_deref())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            texts,
+            vec![
+                Some("junos:changed-localtime=2024-06-10 15:00:00 UTC"),
+                Some("interfaces"),
+                Some("interface ge-0/0/0"),
+                Some("inactive=inactive"),
+                Some("mtu 9192"),
+                Some("disable"),
+                None,
+                None,
+            ]
+        );
+        assert_eq!(parsed.lines[0].parent, None);
+        assert!(matches!(parsed.lines[0].kind, LineKind::Attribute));
+        assert_eq!(
+            parsed.lines[2].raw,
+            "            <interface inactive=\"inactive\">\n                <name>ge-0/0/0</name>"
+        );
+        assert_eq!(parsed.lines[5].raw, "                <disable/>");
+        assert_eq!(parsed.lines[2].flags, NodeFlags::INACTIVE);
+        let Layout::Xml { envelope } = &parsed.layout else {
+            panic!("expected XML layout");
+        };
+        assert_eq!(envelope[0].1, "</rpc-reply>");
+        assert_eq!(envelope[1].1, "    </configuration>");
+    }
+
+    #[test]
+    fn junos_xml_rejects_malformed_documents() {
+        let parsed = junos_xml::parse("<configuration><system></configuration>");
+        assert!(parsed.lines.is_empty());
+    }
+
+    #[test]
+    fn junos_xml_detection() {
+        let text = "<rpc-reply>\n<configuration>\n<system/>\n</configuration>\n</rpc-reply>\n";
+        assert_eq!(detect(text).kind, DialectKind::JuniperJunosXml);
+        let brace = "system {\n    host-name r1;\n}\n";
+        assert_ne!(detect(brace).kind, DialectKind::JuniperJunosXml);
+    }
+
+    #[test]
+    fn procurve_contexts_close_at_exit_without_indentation() {
+        let text = "; J9850A Configuration Editor; Created on release #KB.16.10\nhostname \"sw1\"\noobm\nmember 1\nip address dhcp-bootp\nexit\nexit\nvlan 10\nname \"Users\"\ntagged Trk1\nexit\nspanning-tree\n";
+        let parsed = procurve::parse(text);
+        let find = |needle: &str| {
+            parsed
+                .lines
+                .iter()
+                .position(|line| line.match_text.as_deref() == Some(needle))
+                .expect(needle)
+        };
+        assert!(matches!(parsed.lines[0].kind, LineKind::Comment));
+        assert_eq!(parsed.lines[find("hostname sw1")].parent, None);
+        assert_eq!(parsed.lines[find("member 1")].parent, Some(find("oobm")));
+        assert_eq!(
+            parsed.lines[find("ip address dhcp-bootp")].parent,
+            Some(find("member 1"))
+        );
+        assert_eq!(
+            parsed.lines[find("name Users")].parent,
+            Some(find("vlan 10"))
+        );
+        assert_eq!(parsed.lines[find("spanning-tree")].parent, None);
+        let closings = parsed
+            .lines
+            .iter()
+            .filter(|line| matches!(line.kind, LineKind::Closing))
+            .map(|line| line.parent)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            closings,
+            vec![
+                Some(find("member 1")),
+                Some(find("oobm")),
+                Some(find("vlan 10"))
+            ]
+        );
+    }
+
+    #[test]
+    fn procurve_and_aoscx_detection() {
+        let procurve = "; J9850A Configuration Editor; Created on release #KB.16.10\nhostname \"sw1\"\nvlan 10\n   name \"Users\"\n   exit\n";
+        assert_eq!(detect(procurve).kind, DialectKind::HpeProcurve);
+        let headerless = "hostname \"sw1\"\nvlan 10\n   untagged 1-4\n   exit\n";
+        assert_eq!(detect(headerless).kind, DialectKind::HpeProcurve);
+
+        let aoscx = "!Version ArubaOS-CX GL.10.10.1010\n!export-password: default\nhostname cx1\ninterface 1/1/1\n    no shutdown\n";
+        assert_eq!(detect(aoscx).kind, DialectKind::ArubaAoscx);
+        let idioms = "hostname cx1\ninterface 1/1/1\n    vlan trunk allowed 10,20\n";
+        assert_eq!(detect(idioms).kind, DialectKind::ArubaAoscx);
+
+        let ios = "hostname r1\n!\ninterface Gi0/1\n switchport trunk allowed vlan 10\n!\n";
+        assert_eq!(detect(ios).kind, DialectKind::CiscoIos);
+    }
+
+    #[test]
+    fn junos_quoted_braces_do_not_open_blocks() {
+        let text = "interfaces {\n    ge-0/0/0 {\n        description \"uplink {core}; spare\";\n        mtu 9192;\n    }\n}\nsystem {\n    host-name r1;\n}\n";
+        let parsed = junos::parse(text);
+        let find = |needle: &str| {
+            parsed
+                .lines
+                .iter()
+                .position(|line| line.match_text.as_deref() == Some(needle))
+                .expect(needle)
+        };
+        assert_eq!(
+            parsed.lines[find("description \"uplink {core}; spare\"")].parent,
+            Some(find("ge-0/0/0"))
+        );
+        assert_eq!(
+            parsed.lines[find("mtu 9192")].parent,
+            Some(find("ge-0/0/0"))
+        );
+        assert_eq!(parsed.lines[find("system")].parent, None);
+    }
+
+    #[test]
+    fn junos_block_comments_span_lines() {
+        let text = "/* first\n   second */\nsystem {\n    /* inline */\n    host-name r1; ## trailing\n}\n";
+        let parsed = junos::parse(text);
+        assert!(matches!(parsed.lines[0].kind, LineKind::Comment));
+        assert_eq!(parsed.lines[0].match_text.as_deref(), Some("first second"));
+        assert_eq!(parsed.lines[0].raw, "/* first\n   second */");
+        assert_eq!(parsed.lines[1].match_text.as_deref(), Some("system"));
+        assert!(matches!(parsed.lines[2].kind, LineKind::Comment));
+        assert_eq!(parsed.lines[2].match_text.as_deref(), Some("inline"));
+        assert_eq!(parsed.lines[3].match_text.as_deref(), Some("host-name r1"));
+        assert_eq!(parsed.lines[3].raw, "  host-name r1; ## trailing");
+    }
+
+    #[test]
+    fn junos_inactive_and_protect_become_flags() {
+        let text = "interfaces {\n    inactive: ge-0/0/1 {\n        protect: inactive: disable;\n    }\n}\nprotect: system {\n    host-name r1;\n}\n";
+        let parsed = junos::parse(text);
+        assert_eq!(parsed.lines[1].match_text.as_deref(), Some("ge-0/0/1"));
+        assert_eq!(parsed.lines[1].flags, NodeFlags::INACTIVE);
+        assert_eq!(parsed.lines[2].match_text.as_deref(), Some("disable"));
+        assert_eq!(
+            parsed.lines[2].flags,
+            NodeFlags::INACTIVE.union(NodeFlags::PROTECT)
+        );
+        assert_eq!(parsed.lines[5].match_text.as_deref(), Some("system"));
+        assert_eq!(parsed.lines[5].flags, NodeFlags::PROTECT);
+        assert!(junos::score(text) > 0);
+        assert_eq!(detect(text).kind, DialectKind::JuniperJunos);
+    }
+
+    #[test]
+    fn junos_set_verbs_change_node_state() {
+        let text = "set interfaces ge-0/0/1 description spare\nset interfaces ge-0/0/2 disable\ndeactivate interfaces ge-0/0/1\ndeactivate interfaces ge-0/0/1 description\ndelete interfaces ge-0/0/2\nprotect system\ninsert policy-options policy-statement P term B before term A\n";
+        assert_eq!(detect(text).kind, DialectKind::JuniperJunosSet);
+
+        let parsed = junos_set::parse(text);
+        let find = |needle: &str| {
+            parsed
+                .lines
+                .iter()
+                .position(|line| line.match_text.as_deref() == Some(needle))
+                .expect(needle)
+        };
+        assert_eq!(parsed.lines[find("ge-0/0/1")].flags, NodeFlags::INACTIVE);
+        assert_eq!(
+            parsed.lines[find("description spare")].flags,
+            NodeFlags::INACTIVE
+        );
+        assert_eq!(parsed.lines[find("ge-0/0/2")].flags, NodeFlags::DELETED);
+        assert_eq!(
+            NodeFlags::strip_prefixes("deleted: ge-0/0/2"),
+            (NodeFlags::default(), "deleted: ge-0/0/2")
+        );
+        assert_eq!(parsed.lines[find("disable")].flags, NodeFlags::default());
+
+        let system = find("system");
+        assert_eq!(parsed.lines[system].flags, NodeFlags::PROTECT);
+        assert_eq!(parsed.lines[system].raw, "protect system");
+
+        let hint = find("before term A");
+        assert!(matches!(parsed.lines[hint].kind, LineKind::Attribute));
+        assert_eq!(parsed.lines[hint].parent, Some(find("term B")));
+
+        let trailers = parsed
+            .lines
+            .iter()
+            .filter(|line| matches!(line.kind, LineKind::Closing))
+            .map(|line| line.raw.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            trailers,
+            vec![
+                "deactivate interfaces ge-0/0/1",
+                "deactivate interfaces ge-0/0/1 description",
+                "delete interfaces ge-0/0/2",
+            ]
+        );
+    }
+
+    #[test]
+    fn set_after_delete_restores_the_node() {
+        let text = "delete interfaces ge-0/0/0\nset interfaces ge-0/0/0 mtu 9192\nactivate interfaces ge-0/0/0\n";
+        let parsed = junos_set::parse(text);
+        assert_eq!(parsed.lines[1].match_text.as_deref(), Some("ge-0/0/0"));
+        assert_eq!(parsed.lines[1].flags, NodeFlags::default());
+    }
+
+    #[test]
+    fn junos_set_keeps_quoted_values_and_lists_together() {
+        let text = "set system login message \"authorised  use only\"\nset vlans v10 interface [ ge-0/0/1 ge-0/0/2 ]\nset interfaces ge-0/0/0 description \"to \\\"core\\\" rack\"\n";
+        let parsed = junos_set::parse(text);
+        let texts = parsed
+            .lines
+            .iter()
+            .filter_map(|line| line.match_text.as_deref())
+            .collect::<Vec<_>>();
+        assert!(texts.contains(&"message \"authorised  use only\""));
+        assert!(texts.contains(&"interface [ ge-0/0/1 ge-0/0/2 ]"));
+        assert!(texts.contains(&"description \"to \\\"core\\\" rack\""));
+    }
 }
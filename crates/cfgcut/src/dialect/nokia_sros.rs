@@ -0,0 +1,93 @@
+use super::brace;
+use super::shared::{LineKind, ParsedConfig, unquoted};
+
+pub(super) const HEADER_MARKER: &str = "# TiMOS-";
+const HEADER_SCORE: u32 = 95;
+const STRUCTURE_SCORE: u32 = 60;
+
+pub(super) fn score(text: &str) -> u32 {
+    let has_header = text
+        .lines()
+        .any(|line| line.trim_start().starts_with(HEADER_MARKER));
+    let exits = text
+        .lines()
+        .filter(|line| {
+            let trimmed = line.trim();
+            trimmed == "exit" || trimmed == "exit all"
+        })
+        .count();
+    let braces = brace::score(text) > 0;
+
+    match (has_header, exits > 0, braces) {
+        (true, true, _) | (true, false, false) => HEADER_SCORE,
+        (false, true, false) if text.lines().any(|line| line.trim() == "configure") => {
+            STRUCTURE_SCORE
+        }
+        _ => 0,
+    }
+}
+
+/// Classic CLI blocks close with an `exit` at the opener's indentation;
+/// `exit all` closes every open block at once.
+pub(super) fn parse(text: &str) -> ParsedConfig {
+    let mut parsed = ParsedConfig::default();
+    let mut stack: Vec<(usize, usize)> = Vec::new();
+
+    for line in text.lines() {
+        let raw = line.trim_end();
+        let trimmed = raw.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        let indent = raw.chars().take_while(|c| c.is_whitespace()).count();
+
+        if trimmed == "exit all" {
+            let owner = stack.first().map(|&(_, idx)| idx);
+            stack.clear();
+            parsed.push_line(raw.to_string(), None, LineKind::Closing, owner);
+            continue;
+        }
+
+        if trimmed == "exit" {
+            while let Some(&(prev_indent, _)) = stack.last() {
+                if prev_indent > indent {
+                    stack.pop();
+                } else {
+                    break;
+                }
+            }
+            let owner = stack.pop().map(|(_, idx)| idx);
+            parsed.push_line(raw.to_string(), None, LineKind::Closing, owner);
+            continue;
+        }
+
+        // `echo` lines are section banners generated by the device. Comments
+        // and banners sit at column zero inside `configure`, so they attach
+        // to the open block without closing it.
+        let (kind, match_text) = if let Some(comment) = trimmed.strip_prefix('#') {
+            (LineKind::Comment, comment.trim().to_string())
+        } else if let Some(banner) = trimmed.strip_prefix("echo ") {
+            (LineKind::Comment, unquoted(banner))
+        } else {
+            (LineKind::Command, unquoted(trimmed))
+        };
+
+        if matches!(kind, LineKind::Command) {
+            while let Some(&(prev_indent, _)) = stack.last() {
+                if indent <= prev_indent {
+                    stack.pop();
+                } else {
+                    break;
+                }
+            }
+        }
+        let parent = stack.last().map(|&(_, idx)| idx);
+
+        let idx = parsed.push_line(raw.to_string(), Some(match_text), kind, parent);
+        if matches!(kind, LineKind::Command) {
+            stack.push((indent, idx));
+        }
+    }
+
+    parsed
+}
@@ -0,0 +1,34 @@
+use super::brace::{self, BraceSyntax};
+use super::nokia_sros::HEADER_MARKER;
+use super::shared::ParsedConfig;
+
+const HEADER_SCORE: u32 = 95;
+const ROOT_SCORE: u32 = 70;
+
+const MD_CLI: BraceSyntax = BraceSyntax {
+    comment_prefixes: &["#"],
+    quotes: &['"', '\''],
+    unquote: true,
+    node_flags: false,
+};
+
+pub(super) fn score(text: &str) -> u32 {
+    if brace::score(text) == 0 {
+        return 0;
+    }
+    if text
+        .lines()
+        .any(|line| line.trim_start().starts_with(HEADER_MARKER))
+    {
+        return HEADER_SCORE;
+    }
+    if brace::top_level_blocks(text).contains(&"configure") {
+        ROOT_SCORE
+    } else {
+        0
+    }
+}
+
+pub(super) fn parse(text: &str) -> ParsedConfig {
+    brace::parse_with(text, &MD_CLI)
+}
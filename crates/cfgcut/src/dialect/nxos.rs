@@ -1,10 +1,29 @@
 use super::indent;
 use super::shared::ParsedConfig;
 
-pub(super) fn detect(text: &str) -> bool {
-    text.lines()
-        .map(str::trim_start)
-        .any(|line| line.starts_with("feature ") || line.starts_with("hardware profile"))
+const PLATFORM_MARKERS: &[&str] = &["!Command: show running-config", "NX-OS", "Nexus"];
+const MARKER_SCORE: u32 = 90;
+const FEATURE_SCORE: u32 = 20;
+const FEATURE_CAP: u32 = 60;
+
+pub(super) fn score(text: &str) -> u32 {
+    let lines = indent::structural_lines(text);
+    if lines.iter().any(|line| {
+        indent::is_header_line(line) && PLATFORM_MARKERS.iter().any(|marker| line.contains(marker))
+    }) {
+        return MARKER_SCORE;
+    }
+
+    // Only top-level lines count; `feature` inside a block is an ordinary
+    // sub-command on other platforms.
+    let features = lines
+        .iter()
+        .filter(|line| line.starts_with("feature ") || line.starts_with("hardware profile"))
+        .count();
+    u32::try_from(features)
+        .unwrap_or(u32::MAX)
+        .saturating_mul(FEATURE_SCORE)
+        .min(FEATURE_CAP)
 }
 
 pub(super) fn parse(text: &str) -> ParsedConfig {
@@ -0,0 +1,23 @@
+use super::json;
+use super::shared::ParsedConfig;
+
+const OPENCONFIG_SCORE: u32 = 95;
+const MODULE_SCORE: u32 = 70;
+
+pub(super) fn score(text: &str) -> u32 {
+    let Some(root) = json::top_level_keys(text) else {
+        return 0;
+    };
+    // RFC 7951 qualifies top-level members with their YANG module name.
+    if root.iter().any(|key| key.starts_with("openconfig-")) {
+        OPENCONFIG_SCORE
+    } else if root.iter().any(|key| key.contains(':')) {
+        MODULE_SCORE
+    } else {
+        0
+    }
+}
+
+pub(super) fn parse(text: &str) -> ParsedConfig {
+    json::parse(text)
+}
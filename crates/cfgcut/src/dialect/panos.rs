@@ -0,0 +1,29 @@
+use super::brace::{self, BraceSyntax};
+use super::panos_set;
+use super::shared::ParsedConfig;
+
+/// Wrapper blocks emitted around the configuration by `show config running`.
+const WRAPPERS: &[&str] = &["config", "devices", "localhost.localdomain"];
+const ROOT_SCORE: u32 = 90;
+
+const PANOS: BraceSyntax = BraceSyntax {
+    comment_prefixes: &["#"],
+    quotes: &['"', '\''],
+    unquote: true,
+    node_flags: false,
+};
+
+pub(super) fn score(text: &str) -> u32 {
+    if brace::score(text) == 0 {
+        return 0;
+    }
+    let blocks = brace::top_level_blocks(text);
+    let is_panos = blocks
+        .iter()
+        .any(|block| panos_set::ROOTS.contains(block) || WRAPPERS.contains(block));
+    if is_panos { ROOT_SCORE } else { 0 }
+}
+
+pub(super) fn parse(text: &str) -> ParsedConfig {
+    brace::parse_with(text, &PANOS)
+}
@@ -0,0 +1,132 @@
+use super::shared::{
+    ParsedConfig, SetSegment, build_set_tree, root_share_score, set_statement_roots, split_tokens,
+};
+
+/// Top-level statements of the PAN-OS configuration hierarchy.
+pub(super) const ROOTS: &[&str] = &[
+    "address",
+    "address-group",
+    "application",
+    "application-filter",
+    "application-group",
+    "deviceconfig",
+    "device-group",
+    "external-list",
+    "global-protect",
+    "import",
+    "log-settings",
+    "mgt-config",
+    "network",
+    "profile-group",
+    "profiles",
+    "region",
+    "reports",
+    "rulebase",
+    "schedule",
+    "service",
+    "service-group",
+    "shared",
+    "tag",
+    "template",
+    "template-stack",
+    "vsys",
+    "zone",
+];
+
+/// Leaf attributes that take a value. Container and entry names stay separate
+/// segments so the same patterns match the brace form of the configuration.
+const KEYWORDS_WITH_VALUE: &[&str] = &[
+    "action",
+    "application",
+    "category",
+    "color",
+    "default-gateway",
+    "destination",
+    "destination-port",
+    "dns-setting",
+    "domain",
+    "fqdn",
+    "from",
+    "hostname",
+    "interface",
+    "ip-address",
+    "ip-netmask",
+    "ip-range",
+    "log-setting",
+    "members",
+    "metric",
+    "netmask",
+    "next-vr",
+    "ip-address-family",
+    "port",
+    "primary",
+    "secondary",
+    "service",
+    "source",
+    "source-port",
+    "source-user",
+    "static",
+    "tag",
+    "timezone",
+    "to",
+];
+const KEYWORDS_REST: &[&str] = &["description", "comment"];
+const SET_SCORE: u32 = 90;
+
+pub(super) fn score(text: &str) -> u32 {
+    set_statement_roots(text).map_or(0, |roots| root_share_score(&roots, ROOTS, SET_SCORE))
+}
+
+pub(super) fn parse(text: &str) -> ParsedConfig {
+    build_set_tree(text, split_segments)
+}
+
+fn split_segments(body: &str) -> Vec<SetSegment> {
+    let tokens = split_tokens(body);
+    let mut segments = Vec::new();
+    let mut idx = 0;
+
+    while idx < tokens.len() {
+        let token = &tokens[idx];
+        // The first word is always a root container, even when it shares a
+        // name with a leaf attribute (`service`, `application`, `tag`).
+        let is_root = idx == 0;
+        let keyword = token.raw.as_str();
+
+        if !is_root && KEYWORDS_REST.contains(&keyword) {
+            let rest = &tokens[idx..];
+            segments.push(SetSegment {
+                raw: rest
+                    .iter()
+                    .map(|t| t.raw.as_str())
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                text: rest
+                    .iter()
+                    .map(|t| t.text.as_str())
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            });
+            break;
+        }
+
+        // Attributes only pair with their value at the end of the statement;
+        // earlier occurrences are containers (`network interface ethernet`).
+        if !is_root && idx + 2 == tokens.len() && KEYWORDS_WITH_VALUE.contains(&keyword) {
+            let value = &tokens[idx + 1];
+            segments.push(SetSegment {
+                raw: format!("{} {}", token.raw, value.raw),
+                text: format!("{} {}", token.text, value.text),
+            });
+            break;
+        }
+
+        segments.push(SetSegment {
+            raw: token.raw.clone(),
+            text: token.text.clone(),
+        });
+        idx += 1;
+    }
+
+    segments
+}
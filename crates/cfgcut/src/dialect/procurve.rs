@@ -0,0 +1,90 @@
+use super::shared::{LineKind, ParsedConfig, unquoted};
+
+const HEADER_MARKER: &str = "Configuration Editor";
+const HEADER_SCORE: u32 = 95;
+const STRUCTURE_SCORE: u32 = 70;
+
+/// Statements that open a context closed by `exit`. Saved configurations
+/// indent context bodies, but pasted or hand-edited ones often do not, so
+/// these are recognised by keyword.
+const CONTEXTS: &[&str] = &[
+    "vlan ",
+    "interface ",
+    "oobm",
+    "member ",
+    "router ",
+    "class ",
+    "policy ",
+    "tunnel ",
+    "aaa port-access ",
+];
+
+/// VLAN port membership, which ProCurve writes as `tagged` and `untagged`
+/// lines rather than per-interface switchport commands.
+const MEMBERSHIP: &[&str] = &["tagged ", "untagged ", "no untagged "];
+
+pub(super) fn score(text: &str) -> u32 {
+    let mut exits = false;
+    let mut membership = false;
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with(';') && trimmed.contains(HEADER_MARKER) {
+            return HEADER_SCORE;
+        }
+        exits |= trimmed == "exit";
+        membership |= MEMBERSHIP.iter().any(|prefix| trimmed.starts_with(prefix));
+    }
+    if exits && membership {
+        STRUCTURE_SCORE
+    } else {
+        0
+    }
+}
+
+/// Contexts are rebuilt from `exit` rather than indentation: a context
+/// statement (see [`CONTEXTS`]) opens a block that every following line
+/// belongs to until the matching `exit`. `;` lines are comments, and quoted
+/// names match without their quotes.
+pub(super) fn parse(text: &str) -> ParsedConfig {
+    let mut parsed = ParsedConfig::default();
+    let mut stack: Vec<usize> = Vec::new();
+
+    for line in text.lines() {
+        let raw = line.trim_end();
+        let trimmed = raw.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+
+        if let Some(comment) = trimmed.strip_prefix(';') {
+            parsed.push_line(
+                raw.to_string(),
+                Some(comment.trim().to_string()),
+                LineKind::Comment,
+                stack.last().copied(),
+            );
+            continue;
+        }
+
+        if trimmed == "exit" {
+            let owner = stack.pop();
+            parsed.push_line(raw.to_string(), None, LineKind::Closing, owner);
+            continue;
+        }
+
+        let idx = parsed.push_line(
+            raw.to_string(),
+            Some(unquoted(trimmed)),
+            LineKind::Command,
+            stack.last().copied(),
+        );
+        if CONTEXTS
+            .iter()
+            .any(|context| trimmed == context.trim_end() || trimmed.starts_with(context))
+        {
+            stack.push(idx);
+        }
+    }
+
+    parsed
+}
@@ -0,0 +1,130 @@
+use super::shared::{LineKind, ParsedConfig, split_tokens};
+
+const HEADER_MARKER: &str = " by RouterOS ";
+const HEADER_SCORE: u32 = 95;
+const EXPORT_SCORE: u32 = 70;
+
+/// Menu commands that end the path part of an inline `/path verb ...` line.
+const VERBS: &[&str] = &[
+    "add", "set", "remove", "unset", "enable", "disable", "move", "print", "export", "edit",
+    "comment", "reset",
+];
+
+pub(super) fn score(text: &str) -> u32 {
+    let mut paths = 0usize;
+    let mut commands = 0usize;
+
+    for line in text.lines() {
+        if line.starts_with('#') && line.contains(HEADER_MARKER) {
+            return HEADER_SCORE;
+        }
+        if line.starts_with('/') && line[1..].starts_with(|c: char| c.is_ascii_alphabetic()) {
+            paths += 1;
+        } else if line.starts_with("add ") || line.starts_with("set ") {
+            commands += 1;
+        }
+    }
+
+    if paths > 0 && commands > 0 {
+        EXPORT_SCORE
+    } else {
+        0
+    }
+}
+
+/// `/path` headers become roots and the `add`/`set` lines that follow nest
+/// beneath them. Lines ending in `\` are joined with their continuation into
+/// one entry, and every `key=value` pair becomes a hidden attribute child so
+/// segments can select on individual properties.
+pub(super) fn parse(text: &str) -> ParsedConfig {
+    let mut parsed = ParsedConfig::default();
+    let mut section: Option<usize> = None;
+    let mut lines = text.lines();
+
+    while let Some(first) = lines.next() {
+        let mut raw = first.trim_end().to_string();
+        let mut logical = raw.trim().to_string();
+        while let Some(stripped) = logical.strip_suffix('\\') {
+            let Some(next) = lines.next() else {
+                logical = stripped.trim_end().to_string();
+                break;
+            };
+            raw.push('\n');
+            raw.push_str(next.trim_end());
+            logical = format!("{} {}", stripped.trim_end(), next.trim());
+        }
+
+        if logical.is_empty() {
+            continue;
+        }
+        if let Some(comment) = logical.strip_prefix('#') {
+            parsed.push_line(
+                raw,
+                Some(comment.trim().to_string()),
+                LineKind::Comment,
+                section,
+            );
+            continue;
+        }
+
+        if !logical.starts_with('/') {
+            push_command(&mut parsed, raw, &logical, section);
+            continue;
+        }
+
+        match split_inline(&logical) {
+            Some((path, command)) => {
+                let header = parsed.push_line(
+                    path.to_string(),
+                    Some(path.to_string()),
+                    LineKind::Command,
+                    None,
+                );
+                let indent = raw.len() - raw.trim_start().len();
+                let body = format!("{}{command}", &raw[..indent]);
+                push_command(&mut parsed, body, command, Some(header));
+                section = Some(header);
+            }
+            None => {
+                section = Some(parsed.push_line(raw, Some(logical), LineKind::Command, None));
+            }
+        }
+    }
+
+    parsed
+}
+
+fn push_command(parsed: &mut ParsedConfig, raw: String, logical: &str, parent: Option<usize>) {
+    let tokens = split_tokens(logical);
+    let match_text = tokens
+        .iter()
+        .map(|token| token.text.as_str())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let idx = parsed.push_line(raw, Some(match_text), LineKind::Command, parent);
+
+    for token in tokens.iter().skip(1) {
+        if token.text.contains('=') && !token.text.starts_with('[') {
+            parsed.push_line(
+                String::new(),
+                Some(token.text.clone()),
+                LineKind::Attribute,
+                Some(idx),
+            );
+        }
+    }
+}
+
+/// Split `/ip address add ...` into the menu path and the command that
+/// follows it, or `None` when the line is a bare path header.
+fn split_inline(line: &str) -> Option<(&str, &str)> {
+    let mut offset = 0;
+    for word in line.split_whitespace() {
+        let start = offset + line[offset..].find(word)?;
+        if VERBS.contains(&word) {
+            return Some((line[..start].trim_end(), &line[start..]));
+        }
+        offset = start + word.len();
+    }
+    None
+}
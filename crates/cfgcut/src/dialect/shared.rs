@@ -1,8 +1,12 @@
+use std::collections::HashMap;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum LineKind {
     Command,
     Comment,
     Closing,
+    /// Matchable metadata attached to its parent that is never rendered.
+    Attribute,
 }
 
 #[derive(Debug, Clone)]
@@ -11,12 +15,81 @@ pub struct LineEntry {
     pub match_text: Option<String>,
     pub kind: LineKind,
     pub parent: Option<usize>,
+    pub flags: NodeFlags,
+    /// The 1-based line of the parsed text this entry was read from, if it
+    /// appears there as written.
+    pub line: Option<usize>,
+}
+
+/// State a statement carries apart from its text, such as Junos `inactive:`.
+/// Pattern segments select on it with the same prefix.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct NodeFlags(u8);
+
+impl NodeFlags {
+    pub const INACTIVE: Self = Self(1);
+    pub const PROTECT: Self = Self(1 << 1);
+    /// Named by a `delete` statement in a flattened configuration. Junos
+    /// writes no prefix for it, so it has none in [`Self::PREFIXES`].
+    pub const DELETED: Self = Self(1 << 2);
+
+    /// Statement prefixes Junos writes and the flag each one sets.
+    pub const PREFIXES: &[(&str, Self)] =
+        &[("inactive:", Self::INACTIVE), ("protect:", Self::PROTECT)];
+
+    #[must_use]
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    #[must_use]
+    pub const fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    #[must_use]
+    pub const fn without(self, other: Self) -> Self {
+        Self(self.0 & !other.0)
+    }
+
+    /// Split leading flag prefixes off `text`, returning the flags and the
+    /// rest of the text.
+    #[must_use]
+    pub fn strip_prefixes(mut text: &str) -> (Self, &str) {
+        let mut flags = Self::default();
+        'prefixes: loop {
+            for &(prefix, flag) in Self::PREFIXES {
+                if let Some(rest) = text.strip_prefix(prefix) {
+                    flags = flags.union(flag);
+                    text = rest.trim_start();
+                    continue 'prefixes;
+                }
+            }
+            return (flags, text);
+        }
+    }
+}
+
+/// How selected lines are stitched back together on output.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub enum Layout {
+    /// Each line stands on its own.
+    #[default]
+    Lines,
+    /// Lines are JSON members: siblings need separating commas and the
+    /// selection is wrapped in the document's root object or array.
+    Json { root_array: bool },
+    /// Lines are XML elements: the selection is wrapped in the envelope
+    /// elements, given as indented start and end tags, that enclose the
+    /// configuration.
+    Xml { envelope: Vec<(String, String)> },
 }
 
 #[derive(Debug, Default, Clone)]
 pub struct ParsedConfig {
     pub lines: Vec<LineEntry>,
     pub children: Vec<Vec<usize>>,
+    pub layout: Layout,
 }
 
 impl ParsedConfig {
@@ -33,6 +106,8 @@ impl ParsedConfig {
             match_text,
             kind,
             parent,
+            flags: NodeFlags::default(),
+            line: None,
         });
         self.children.push(Vec::new());
         if let Some(parent_idx) = parent
@@ -43,6 +118,41 @@ impl ParsedConfig {
         idx
     }
 
+    /// Find the line of `text` each command and comment was read from by
+    /// matching its first line against the source in order. Parsers re-indent
+    /// lines and JSON members lose their trailing commas, so both sides are
+    /// compared trimmed. Entries a parser synthesised, such as the
+    /// intermediate statements of a `set` tree, are not found and keep
+    /// `None`. Only needed for token records, so callers run it on demand.
+    pub fn locate_lines(&mut self, text: &str) {
+        // Every source line's positions, ascending, so each lookup is a
+        // binary search rather than a scan that a miss would run to the end.
+        let mut positions: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (idx, line) in text.lines().enumerate() {
+            positions
+                .entry(line.trim().trim_end_matches(','))
+                .or_default()
+                .push(idx);
+        }
+        let mut next = 0;
+        for entry in &mut self.lines {
+            if !matches!(entry.kind, LineKind::Command | LineKind::Comment) {
+                continue;
+            }
+            let first = entry.raw.lines().next().unwrap_or_default().trim();
+            if first.is_empty() {
+                continue;
+            }
+            let Some(found) = positions.get(first) else {
+                continue;
+            };
+            if let Some(&idx) = found.get(found.partition_point(|&idx| idx < next)) {
+                entry.line = Some(idx + 1);
+                next = idx + 1;
+            }
+        }
+    }
+
     #[cfg_attr(not(any(test, feature = "fuzzing")), allow(dead_code))]
     pub fn from_text(text: &str) -> Self {
         let (_, parsed) = super::parse_with_detect(text);
@@ -73,3 +183,351 @@ pub fn is_comment(line: &str) -> bool {
     let trimmed = line.trim_start();
     trimmed.starts_with('!') || trimmed.starts_with('#')
 }
+
+/// A whitespace-delimited word that may have been quoted or bracketed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    /// The word as written, including quotes and list brackets.
+    pub raw: String,
+    /// The word with quoting removed, used for matching.
+    pub text: String,
+}
+
+/// Split a statement into words, keeping quoted strings and `[ ... ]` lists
+/// together as single tokens.
+pub fn split_tokens(line: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = line.chars().peekable();
+
+    while let Some(&ch) = chars.peek() {
+        if ch.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        if ch == '[' {
+            chars.next();
+            let mut raw = vec![String::from("[")];
+            let mut text = vec![String::from("[")];
+            while let Some(&inner) = chars.peek() {
+                if inner.is_whitespace() {
+                    chars.next();
+                    continue;
+                }
+                if inner == ']' {
+                    chars.next();
+                    break;
+                }
+                let word = read_word(&mut chars, true);
+                raw.push(word.raw);
+                text.push(word.text);
+            }
+            raw.push(String::from("]"));
+            text.push(String::from("]"));
+            tokens.push(Token {
+                raw: raw.join(" "),
+                text: text.join(" "),
+            });
+            continue;
+        }
+
+        tokens.push(read_word(&mut chars, false));
+    }
+
+    tokens
+}
+
+fn read_word(chars: &mut std::iter::Peekable<std::str::Chars<'_>>, in_list: bool) -> Token {
+    let mut raw = String::new();
+    let mut text = String::new();
+    let mut quote: Option<char> = None;
+
+    while let Some(&ch) = chars.peek() {
+        match quote {
+            Some(open) => {
+                chars.next();
+                raw.push(ch);
+                if ch == '\\' && open == '"' {
+                    if let Some(escaped) = chars.next() {
+                        raw.push(escaped);
+                        text.push(escaped);
+                    }
+                } else if ch == open {
+                    quote = None;
+                } else {
+                    text.push(ch);
+                }
+            }
+            None => {
+                if ch.is_whitespace() || (in_list && ch == ']') {
+                    break;
+                }
+                chars.next();
+                raw.push(ch);
+                if ch == '"' || ch == '\'' {
+                    quote = Some(ch);
+                } else {
+                    text.push(ch);
+                }
+            }
+        }
+    }
+
+    Token { raw, text }
+}
+
+/// Join the unquoted form of every token in `line`.
+pub fn unquoted(line: &str) -> String {
+    split_tokens(line)
+        .into_iter()
+        .map(|token| token.text)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// One hierarchy level of a flattened `set` statement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SetSegment {
+    /// The segment as written, used to rebuild intermediate `set` lines.
+    pub raw: String,
+    /// The segment used for matching.
+    pub text: String,
+}
+
+impl SetSegment {
+    pub fn plain(text: String) -> Self {
+        Self {
+            raw: text.clone(),
+            text,
+        }
+    }
+}
+
+/// Verbs that can start a flattened statement. `show configuration | display
+/// set` and change scripts mix `set` with the others.
+const STATEMENT_VERBS: &[&str] = &[
+    "set",
+    "delete",
+    "deactivate",
+    "activate",
+    "protect",
+    "unprotect",
+    "insert",
+];
+
+fn statement_verb(line: &str) -> Option<(&str, &str)> {
+    let (verb, body) = line.split_once(char::is_whitespace)?;
+    STATEMENT_VERBS
+        .contains(&verb)
+        .then(|| (verb, body.trim_start()))
+}
+
+/// Build a hierarchy from flattened statements. Every distinct `set` path
+/// prefix becomes a node and the leaf keeps the original line. The other
+/// verbs change the state of the node they name: `deactivate`/`activate`
+/// and `protect`/`unprotect` toggle its flags, `delete` marks it deleted
+/// until a later `set` restores it, and `insert` records its ordering hint
+/// as an attribute. Those lines are kept as closing lines of their node, so
+/// they are printed whenever the node is.
+pub fn build_set_tree(text: &str, split: fn(&str) -> Vec<SetSegment>) -> ParsedConfig {
+    let mut tree = SetTree::default();
+
+    for raw_line in text.lines() {
+        let trimmed = raw_line.trim_end();
+        if trimmed.trim().is_empty() {
+            continue;
+        }
+        let trimmed_start = trimmed.trim_start();
+        if trimmed_start.starts_with('#') {
+            let idx = tree.parsed.push_line(
+                trimmed_start.to_string(),
+                Some(trimmed_start.trim_start_matches('#').trim().to_string()),
+                LineKind::Comment,
+                None,
+            );
+            tree.node_map.insert(vec![format!("comment:{idx}")], idx);
+            continue;
+        }
+        let Some((verb, body)) = statement_verb(trimmed_start) else {
+            continue;
+        };
+
+        if verb == "set" {
+            let segments = split(body);
+            if !segments.is_empty() {
+                tree.set(&segments, trimmed_start);
+            }
+            continue;
+        }
+
+        let (path, hint) = if verb == "insert" {
+            match split_insert(body) {
+                Some((path, hint)) => (path, Some(hint)),
+                None => continue,
+            }
+        } else {
+            (body, None)
+        };
+        let segments = split(path);
+        if segments.is_empty() {
+            continue;
+        }
+        let idx = tree.target(&segments, trimmed_start);
+        let entry = &mut tree.parsed.lines[idx];
+        entry.flags = match verb {
+            "deactivate" => entry.flags.union(NodeFlags::INACTIVE),
+            "activate" => entry.flags.without(NodeFlags::INACTIVE),
+            "protect" => entry.flags.union(NodeFlags::PROTECT),
+            "unprotect" => entry.flags.without(NodeFlags::PROTECT),
+            "delete" => entry.flags.union(NodeFlags::DELETED),
+            _ => entry.flags,
+        };
+        if let Some(hint) = hint {
+            tree.parsed.push_line(
+                String::new(),
+                Some(hint.to_string()),
+                LineKind::Attribute,
+                Some(idx),
+            );
+        }
+    }
+
+    tree.parsed
+}
+
+/// Split `insert <path> before|after <sibling>` into the path and the hint.
+fn split_insert(body: &str) -> Option<(&str, &str)> {
+    [" before ", " after "]
+        .iter()
+        .filter_map(|keyword| body.find(keyword))
+        .min()
+        .map(|pos| (&body[..pos], body[pos + 1..].trim()))
+}
+
+#[derive(Default)]
+struct SetTree {
+    parsed: ParsedConfig,
+    node_map: HashMap<Vec<String>, usize>,
+}
+
+impl SetTree {
+    /// Add a `set` statement, reviving any deleted node along its path.
+    fn set(&mut self, segments: &[SetSegment], line: &str) {
+        let mut path = Vec::new();
+        let mut parent = None;
+        for (idx, segment) in segments.iter().enumerate() {
+            path.push(segment.raw.clone());
+            let node_idx = match self.node_map.get(&path) {
+                Some(&existing) => existing,
+                None => {
+                    let raw = if idx + 1 == segments.len() {
+                        line.to_string()
+                    } else {
+                        format!("set {}", path.join(" "))
+                    };
+                    self.push_node(&path, raw, &segment.text, parent)
+                }
+            };
+            let entry = &mut self.parsed.lines[node_idx];
+            entry.flags = entry.flags.without(NodeFlags::DELETED);
+            parent = Some(node_idx);
+        }
+    }
+
+    /// Find the node another verb names, creating it when no `set` did. A
+    /// final keyword such as `description` also names the statement that
+    /// carries its value.
+    fn target(&mut self, segments: &[SetSegment], line: &str) -> usize {
+        let mut path = Vec::new();
+        let mut parent = None;
+        for (idx, segment) in segments.iter().enumerate() {
+            path.push(segment.raw.clone());
+            let last = idx + 1 == segments.len();
+            if let Some(&existing) = self.node_map.get(&path) {
+                parent = Some(existing);
+                continue;
+            }
+            if last && let Some(valued) = self.valued_child(parent, &segment.text) {
+                parent = Some(valued);
+                break;
+            }
+
+            let raw = if last {
+                line.to_string()
+            } else {
+                format!("set {}", path.join(" "))
+            };
+            let node_idx = self.push_node(&path, raw, &segment.text, parent);
+            if last {
+                return node_idx;
+            }
+            parent = Some(node_idx);
+        }
+
+        let idx = parent.unwrap_or_default();
+        self.parsed
+            .push_line(line.to_string(), None, LineKind::Closing, Some(idx));
+        idx
+    }
+
+    fn valued_child(&self, parent: Option<usize>, keyword: &str) -> Option<usize> {
+        let prefix = format!("{keyword} ");
+        let siblings = match parent {
+            Some(parent) => self.parsed.children[parent].clone(),
+            None => (0..self.parsed.lines.len())
+                .filter(|&idx| self.parsed.lines[idx].parent.is_none())
+                .collect(),
+        };
+        siblings.into_iter().find(|&idx| {
+            let line = &self.parsed.lines[idx];
+            matches!(line.kind, LineKind::Command)
+                && line
+                    .match_text
+                    .as_deref()
+                    .is_some_and(|text| text.starts_with(&prefix))
+        })
+    }
+
+    fn push_node(
+        &mut self,
+        path: &[String],
+        raw: String,
+        text: &str,
+        parent: Option<usize>,
+    ) -> usize {
+        let node_idx =
+            self.parsed
+                .push_line(raw, Some(text.to_string()), LineKind::Command, parent);
+        self.node_map.insert(path.to_vec(), node_idx);
+        node_idx
+    }
+}
+
+/// The first keyword of every flattened statement, or `None` when the text
+/// contains lines that are not statements (comments excepted).
+pub fn set_statement_roots(text: &str) -> Option<Vec<&str>> {
+    let mut roots = Vec::new();
+
+    for raw in text.lines() {
+        let trimmed = raw.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        // Any meaningful line that does not begin with a verb rules out set syntax.
+        let (_, body) = statement_verb(trimmed)?;
+        roots.push(body.split_whitespace().next().unwrap_or_default());
+    }
+
+    (!roots.is_empty()).then_some(roots)
+}
+
+/// Scale `max` by the share of `roots` found in `known`.
+pub fn root_share_score(roots: &[&str], known: &[&str], max: u32) -> u32 {
+    let total = u32::try_from(roots.len()).unwrap_or(u32::MAX);
+    if total == 0 {
+        return 0;
+    }
+    let hits = roots.iter().filter(|root| known.contains(root)).count();
+    let hits = u32::try_from(hits).unwrap_or(u32::MAX);
+    max.saturating_mul(hits) / total
+}
@@ -0,0 +1,26 @@
+use super::json;
+use super::shared::ParsedConfig;
+
+const METADATA_TABLE: &str = "DEVICE_METADATA";
+const METADATA_SCORE: u32 = 95;
+const TABLE_SCORE: u32 = 80;
+
+pub(super) fn score(text: &str) -> u32 {
+    let Some(tables) = json::top_level_keys(text) else {
+        return 0;
+    };
+    if tables.iter().any(|table| table == METADATA_TABLE) {
+        return METADATA_SCORE;
+    }
+    // `config_db.json` tables are named in upper snake case (`PORT`, `VLAN_MEMBER`).
+    let all_tables = !tables.is_empty()
+        && tables.iter().all(|key| {
+            key.chars()
+                .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+        });
+    if all_tables { TABLE_SCORE } else { 0 }
+}
+
+pub(super) fn parse(text: &str) -> ParsedConfig {
+    json::parse(text)
+}
@@ -0,0 +1,119 @@
+use super::shared::{LineKind, ParsedConfig};
+
+const VERSION_MARKER: &str = "!Software Version V";
+const VERSION_SCORE: u32 = 95;
+const SYSNAME_SCORE: u32 = 80;
+const IDIOM_SCORE: u32 = 50;
+
+pub(super) fn score(text: &str) -> u32 {
+    let mut separators = 0usize;
+    let mut undo = 0usize;
+
+    for line in text.lines() {
+        if line.starts_with(VERSION_MARKER) {
+            return VERSION_SCORE;
+        }
+        if line.starts_with("sysname ") {
+            return SYSNAME_SCORE;
+        }
+        let trimmed = line.trim();
+        if trimmed == "#" {
+            separators += 1;
+        } else if trimmed.starts_with("undo ") {
+            undo += 1;
+        }
+    }
+
+    if separators >= 2 && undo > 0 {
+        IDIOM_SCORE
+    } else {
+        0
+    }
+}
+
+/// Bare `#` lines end the section at their indentation, `quit` closes the
+/// enclosing view, and `return` leaves every view. All three become closing
+/// lines; `!` starts a comment.
+pub(super) fn parse(text: &str) -> ParsedConfig {
+    let mut parsed = ParsedConfig::default();
+    let mut stack: Vec<(usize, usize)> = Vec::new();
+
+    for line in text.lines() {
+        let raw = line.trim_end();
+        let trimmed = raw.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        let indent = raw.chars().take_while(|c| c.is_whitespace()).count();
+
+        match trimmed {
+            "#" => {
+                while let Some(&(prev_indent, _)) = stack.last() {
+                    if prev_indent > indent {
+                        stack.pop();
+                    } else {
+                        break;
+                    }
+                }
+                let owner = match stack.last() {
+                    Some(&(prev_indent, idx)) if prev_indent == indent => {
+                        stack.pop();
+                        Some(idx)
+                    }
+                    _ => None,
+                };
+                parsed.push_line(raw.to_string(), None, LineKind::Closing, owner);
+            }
+            "quit" => {
+                let owner = if indent == 0 {
+                    let root = stack.first().map(|&(_, idx)| idx);
+                    stack.clear();
+                    root
+                } else {
+                    while let Some(&(prev_indent, _)) = stack.last() {
+                        if prev_indent >= indent {
+                            stack.pop();
+                        } else {
+                            break;
+                        }
+                    }
+                    stack.pop().map(|(_, idx)| idx)
+                };
+                parsed.push_line(raw.to_string(), None, LineKind::Closing, owner);
+            }
+            "return" => {
+                let owner = stack.first().map(|&(_, idx)| idx);
+                stack.clear();
+                parsed.push_line(raw.to_string(), None, LineKind::Closing, owner);
+            }
+            _ => {
+                while let Some(&(prev_indent, _)) = stack.last() {
+                    if indent <= prev_indent {
+                        stack.pop();
+                    } else {
+                        break;
+                    }
+                }
+                let parent = stack.last().map(|&(_, idx)| idx);
+                if let Some(comment) = trimmed.strip_prefix('!') {
+                    parsed.push_line(
+                        raw.to_string(),
+                        Some(comment.trim().to_string()),
+                        LineKind::Comment,
+                        parent,
+                    );
+                } else {
+                    let idx = parsed.push_line(
+                        raw.to_string(),
+                        Some(trimmed.to_string()),
+                        LineKind::Command,
+                        parent,
+                    );
+                    stack.push((indent, idx));
+                }
+            }
+        }
+    }
+
+    parsed
+}
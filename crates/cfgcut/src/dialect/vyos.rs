@@ -0,0 +1,60 @@
+use super::brace::{self, BraceSyntax};
+use super::shared::ParsedConfig;
+
+/// Footer written under every saved `config.boot`, in `/* */` form on VyOS
+/// 1.2/1.3 and EdgeOS and in `//` form on VyOS 1.4 and later.
+const FOOTER_MARKER: &str = "Warning: Do not remove the following line.";
+const FOOTER_SCORE: u32 = 95;
+const ETHERNET_SCORE: u32 = 80;
+
+const VYOS: BraceSyntax = BraceSyntax {
+    comment_prefixes: &["/*", "//"],
+    quotes: &['"', '\''],
+    unquote: true,
+    node_flags: false,
+};
+
+pub(super) fn score(text: &str) -> u32 {
+    if brace::score(text) == 0 {
+        return 0;
+    }
+    if has_footer(text) {
+        return FOOTER_SCORE;
+    }
+    if text.lines().any(|line| is_ethernet_node(line.trim())) {
+        ETHERNET_SCORE
+    } else {
+        0
+    }
+}
+
+pub(super) fn parse(text: &str) -> ParsedConfig {
+    brace::parse_with(text, &VYOS)
+}
+
+/// Whether the text carries VyOS-only evidence, used by the VyOS set dialect
+/// to tell flattened VyOS configurations from Junos ones, whose root
+/// statements they share.
+pub(super) fn has_markers(text: &str) -> bool {
+    has_footer(text)
+        || text.lines().any(|line| {
+            let trimmed = line.trim();
+            is_ethernet_node(trimmed)
+                || trimmed
+                    .strip_prefix("set interfaces ")
+                    .is_some_and(is_ethernet_node)
+        })
+}
+
+fn has_footer(text: &str) -> bool {
+    text.lines().any(|line| {
+        let trimmed = line.trim_start();
+        (trimmed.starts_with("/*") || trimmed.starts_with("//")) && trimmed.contains(FOOTER_MARKER)
+    })
+}
+
+/// `ethernet eth0` as a block header or `set interfaces` path segment.
+fn is_ethernet_node(text: &str) -> bool {
+    text.strip_prefix("ethernet eth")
+        .is_some_and(|rest| rest.starts_with(|c: char| c.is_ascii_digit()))
+}
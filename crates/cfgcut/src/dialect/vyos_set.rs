@@ -0,0 +1,107 @@
+use super::shared::{
+    ParsedConfig, SetSegment, Token, build_set_tree, set_statement_roots, split_tokens,
+};
+use super::vyos;
+
+const MARKER_SCORE: u32 = 95;
+
+/// Tag nodes whose name follows the keyword, matching block headers such as
+/// `ethernet eth0 {` and `rule 10 {` in the brace form.
+const TAG_NODES: &[&str] = &[
+    "address-group",
+    "area",
+    "bgp",
+    "bonding",
+    "bridge",
+    "dummy",
+    "ethernet",
+    "interface",
+    "loopback",
+    "name",
+    "neighbor",
+    "network",
+    "network-group",
+    "next-hop",
+    "peer-group",
+    "port-group",
+    "prefix-list",
+    "route",
+    "route-map",
+    "rule",
+    "server",
+    "tunnel",
+    "user",
+    "vif",
+    "vrf",
+    "vti",
+    "wireguard",
+];
+
+/// Leaf attributes whose value may be written without quotes.
+const KEYWORDS_WITH_VALUE: &[&str] = &[
+    "action",
+    "address",
+    "description",
+    "destination",
+    "host-name",
+    "hw-id",
+    "mtu",
+    "port",
+    "protocol",
+    "remote-as",
+    "source",
+    "time-zone",
+];
+
+pub(super) fn score(text: &str) -> u32 {
+    if set_statement_roots(text).is_none() {
+        return 0;
+    }
+    let quoted = text
+        .lines()
+        .any(|line| line.trim_end().ends_with('\'') && line.trim_start().starts_with("set "));
+    if quoted || vyos::has_markers(text) {
+        MARKER_SCORE
+    } else {
+        0
+    }
+}
+
+pub(super) fn parse(text: &str) -> ParsedConfig {
+    build_set_tree(text, split_segments)
+}
+
+fn split_segments(body: &str) -> Vec<SetSegment> {
+    let tokens = split_tokens(body);
+    let mut segments = Vec::new();
+    let mut idx = 0;
+
+    while idx < tokens.len() {
+        let token = &tokens[idx];
+        let keyword = token.raw.as_str();
+        let is_leaf_value = idx + 2 == tokens.len()
+            && (is_quoted(&tokens[idx + 1]) || KEYWORDS_WITH_VALUE.contains(&keyword));
+
+        if idx > 0 && idx + 1 < tokens.len() && (is_leaf_value || TAG_NODES.contains(&keyword)) {
+            let value = &tokens[idx + 1];
+            segments.push(SetSegment {
+                raw: format!("{} {}", token.raw, value.raw),
+                text: format!("{} {}", token.text, value.text),
+            });
+            idx += 2;
+            continue;
+        }
+
+        segments.push(SetSegment {
+            raw: token.raw.clone(),
+            text: token.text.clone(),
+        });
+        idx += 1;
+    }
+
+    segments
+}
+
+fn is_quoted(token: &Token) -> bool {
+    token.raw.starts_with('\'') || token.raw.starts_with('"')
+}
@@ -0,0 +1,227 @@
+use super::shared::{LineKind, ParsedConfig};
+
+const NVUE_SCORE: u32 = 95;
+const NETPLAN_SCORE: u32 = 90;
+const GENERIC_SCORE: u32 = 40;
+
+pub(super) fn score(text: &str) -> u32 {
+    let mut lines = text
+        .lines()
+        .map(str::trim_end)
+        .filter(|line| !line.trim().is_empty() && !is_comment(line.trim_start()));
+    let Some(first) = lines.next() else {
+        return 0;
+    };
+    if first == "- header:" || first == "- set:" {
+        return NVUE_SCORE;
+    }
+    if first == "network:" {
+        return NETPLAN_SCORE;
+    }
+
+    let mut total = 1usize;
+    let mut shaped = usize::from(entry(first.trim_start()).is_some());
+    let mut keys = usize::from(first.ends_with(':'));
+    for line in lines {
+        total += 1;
+        let trimmed = line.trim_start();
+        if entry(trimmed).is_some() {
+            shaped += 1;
+        }
+        if trimmed.ends_with(':') {
+            keys += 1;
+        }
+    }
+    // Most lines must look like YAML, and at least one must open a mapping.
+    if keys > 0 && shaped * 5 >= total * 4 {
+        GENERIC_SCORE
+    } else {
+        0
+    }
+}
+
+/// Mapping keys and sequence items become nodes nested by indentation.
+/// `key:` blocks match on `key`, scalar pairs on `key: value`, and `- item`
+/// entries on their content; a sequence item that starts a mapping owns the
+/// rest of that mapping. Lines are kept as written, so extracted sections are
+/// valid YAML with their parent keys.
+pub(super) fn parse(text: &str) -> ParsedConfig {
+    let mut parsed = ParsedConfig::default();
+    let mut stack: Vec<Frame> = Vec::new();
+    let mut block_scalar: Option<(usize, usize)> = None;
+
+    for line in text.lines() {
+        let raw = line.trim_end();
+        let trimmed = raw.trim_start();
+        let indent = raw.len() - trimmed.len();
+
+        if let Some((owner, owner_indent)) = block_scalar {
+            if trimmed.is_empty() {
+                continue;
+            }
+            if indent > owner_indent {
+                parsed.push_line(
+                    raw.to_string(),
+                    Some(trimmed.to_string()),
+                    LineKind::Command,
+                    Some(owner),
+                );
+                continue;
+            }
+            block_scalar = None;
+        }
+
+        if trimmed.is_empty() {
+            continue;
+        }
+        if is_comment(trimmed) {
+            let text = trimmed.trim_start_matches('#').trim().to_string();
+            parsed.push_line(
+                raw.to_string(),
+                Some(text),
+                LineKind::Comment,
+                stack.last().map(|frame| frame.idx),
+            );
+            continue;
+        }
+
+        let is_item = trimmed == "-" || trimmed.starts_with("- ");
+        while let Some(frame) = stack.last() {
+            // Sequences may sit at the same indentation as their parent key.
+            let keeps = frame.indent < indent
+                || (is_item && frame.indent == indent && frame.opens_block && !frame.is_item);
+            if keeps {
+                break;
+            }
+            stack.pop();
+        }
+
+        let Some(Entry {
+            match_text,
+            opens_block,
+            block_scalar: scalar,
+        }) = entry(trimmed)
+        else {
+            continue;
+        };
+        let parent = stack.last().map(|frame| frame.idx);
+        let idx = parsed.push_line(raw.to_string(), Some(match_text), LineKind::Command, parent);
+        stack.push(Frame {
+            indent,
+            idx,
+            opens_block,
+            is_item,
+        });
+        if scalar {
+            block_scalar = Some((idx, indent));
+        }
+    }
+
+    parsed
+}
+
+struct Frame {
+    indent: usize,
+    idx: usize,
+    opens_block: bool,
+    is_item: bool,
+}
+
+struct Entry {
+    match_text: String,
+    /// `key:` with nothing after it, whose value follows on later lines.
+    opens_block: bool,
+    /// `key: |` or `key: >`, whose value is literal text on later lines.
+    block_scalar: bool,
+}
+
+/// Describe one line of YAML, or `None` when it is not a mapping entry or
+/// sequence item.
+fn entry(trimmed: &str) -> Option<Entry> {
+    let line = strip_comment(trimmed);
+    let (is_item, content) = match line.strip_prefix('-') {
+        Some(rest) if rest.is_empty() || rest.starts_with(' ') => (true, rest.trim_start()),
+        _ => (false, line),
+    };
+
+    if let Some((key, value)) = split_key(content) {
+        let key = unquote(key);
+        let value = value.trim();
+        let block_scalar = value.starts_with('|') || value.starts_with('>');
+        return Some(Entry {
+            match_text: if value.is_empty() || block_scalar {
+                key.to_string()
+            } else {
+                format!("{key}: {}", unquote(value))
+            },
+            opens_block: value.is_empty(),
+            block_scalar,
+        });
+    }
+
+    is_item.then(|| Entry {
+        match_text: unquote(content).to_string(),
+        opens_block: content.is_empty(),
+        block_scalar: content.starts_with('|') || content.starts_with('>'),
+    })
+}
+
+/// Split `key: value` at the first `:` outside quotes that ends the line or
+/// is followed by a space.
+fn split_key(content: &str) -> Option<(&str, &str)> {
+    let mut quote: Option<char> = None;
+    for (pos, ch) in content.char_indices() {
+        match quote {
+            Some(open) => {
+                if ch == open {
+                    quote = None;
+                }
+            }
+            None => match ch {
+                '"' | '\'' if pos == 0 => quote = Some(ch),
+                '[' | '{' if pos == 0 => return None,
+                ':' => {
+                    let rest = &content[pos + 1..];
+                    if rest.is_empty() || rest.starts_with(' ') {
+                        return Some((&content[..pos], rest));
+                    }
+                }
+                _ => {}
+            },
+        }
+    }
+    None
+}
+
+/// Drop a trailing ` # comment` that sits outside quotes.
+fn strip_comment(line: &str) -> &str {
+    let mut quote: Option<char> = None;
+    let mut previous = ' ';
+    for (pos, ch) in line.char_indices() {
+        match quote {
+            Some(open) if ch == open => quote = None,
+            Some(_) => {}
+            None => match ch {
+                '"' | '\'' if previous == ' ' || previous == ':' => quote = Some(ch),
+                '#' if previous == ' ' && pos > 0 => return line[..pos].trim_end(),
+                _ => {}
+            },
+        }
+        previous = ch;
+    }
+    line
+}
+
+fn unquote(value: &str) -> &str {
+    let value = value.trim();
+    for quote in ['"', '\''] {
+        if value.len() >= 2 && value.starts_with(quote) && value.ends_with(quote) {
+            return &value[1..value.len() - 1];
+        }
+    }
+    value
+}
+
+fn is_comment(trimmed: &str) -> bool {
+    trimmed.starts_with('#') || matches!(trimmed, "---" | "...")
+}
@@ -0,0 +1,273 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::dialect::{DialectKind, LineKind, NodeFlags, ParsedConfig};
+use crate::line_path;
+
+/// The indentation the brace parser normalises blocks to.
+const BRACE_INDENT: usize = 2;
+
+/// Selects the syntax Junos matches are printed in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum EmitSyntax {
+    /// Print matches as they appear in the source.
+    #[default]
+    Source,
+    /// Print fully qualified `set` statements.
+    Set,
+    /// Print a nested brace hierarchy.
+    Brace,
+}
+
+impl EmitSyntax {
+    /// Returns the name accepted by `--emit`.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Source => "source",
+            Self::Set => "set",
+            Self::Brace => "brace",
+        }
+    }
+
+    /// The dialect a configuration parsed as `source` is converted to, or
+    /// `None` when it is printed as written.
+    pub(crate) const fn target(self, source: DialectKind) -> Option<DialectKind> {
+        match (self, source) {
+            (Self::Set, DialectKind::JuniperJunos) => Some(DialectKind::JuniperJunosSet),
+            (Self::Brace, DialectKind::JuniperJunosSet) => Some(DialectKind::JuniperJunos),
+            _ => None,
+        }
+    }
+
+    /// Whether `source` can be printed in this syntax at all.
+    pub(crate) const fn applies_to(self, source: DialectKind) -> bool {
+        matches!(self, Self::Source)
+            || matches!(
+                source,
+                DialectKind::JuniperJunos | DialectKind::JuniperJunosSet
+            )
+    }
+}
+
+impl fmt::Display for EmitSyntax {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Error returned when an output syntax name is not recognised.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownEmitSyntax {
+    name: String,
+}
+
+impl UnknownEmitSyntax {
+    /// The syntax name supplied by the caller.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl fmt::Display for UnknownEmitSyntax {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "unknown output syntax '{}' (expected one of: source, set, brace)",
+            self.name
+        )
+    }
+}
+
+impl std::error::Error for UnknownEmitSyntax {}
+
+impl FromStr for EmitSyntax {
+    type Err = UnknownEmitSyntax;
+
+    fn from_str(raw: &str) -> Result<Self, Self::Err> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "source" => Ok(Self::Source),
+            "set" => Ok(Self::Set),
+            "brace" | "curly" => Ok(Self::Brace),
+            _ => Err(UnknownEmitSyntax {
+                name: raw.to_string(),
+            }),
+        }
+    }
+}
+
+/// A line of converted output and the source line it was built from.
+pub(crate) struct EmittedLine {
+    pub idx: usize,
+    pub kind: LineKind,
+    pub text: String,
+}
+
+/// Render the selected lines of `config` in the syntax of `target`.
+pub(crate) fn convert(
+    config: &ParsedConfig,
+    ordered: &[usize],
+    target: DialectKind,
+    with_comments: bool,
+) -> Vec<EmittedLine> {
+    let selection = Selection::new(config, ordered, with_comments);
+    let mut out = Vec::new();
+    for root in selection.roots(ordered) {
+        if matches!(target, DialectKind::JuniperJunos) {
+            selection.brace(root, 0, &mut out);
+        } else {
+            selection.set(root, &mut out);
+        }
+    }
+    out
+}
+
+struct Selection<'a> {
+    config: &'a ParsedConfig,
+    selected: HashSet<usize>,
+    with_comments: bool,
+}
+
+impl<'a> Selection<'a> {
+    fn new(config: &'a ParsedConfig, ordered: &[usize], with_comments: bool) -> Self {
+        Self {
+            config,
+            selected: ordered.iter().copied().collect(),
+            with_comments,
+        }
+    }
+
+    /// Selected lines whose parent is not selected, in output order.
+    fn roots<'b>(&'b self, ordered: &'b [usize]) -> impl Iterator<Item = usize> + 'b {
+        ordered.iter().copied().filter(|&idx| {
+            self.config.lines[idx]
+                .parent
+                .is_none_or(|parent| !self.selected.contains(&parent))
+        })
+    }
+
+    fn children(&self, idx: usize) -> impl Iterator<Item = usize> + '_ {
+        self.config.children[idx]
+            .iter()
+            .copied()
+            .filter(|child| self.selected.contains(child))
+    }
+
+    /// Whether a selected statement has selected statements below it, so it
+    /// prints as a block rather than a leaf.
+    fn is_block(&self, idx: usize) -> bool {
+        self.children(idx)
+            .any(|child| matches!(self.config.lines[child].kind, LineKind::Command))
+    }
+
+    fn comment(&self, idx: usize) -> Option<&str> {
+        let line = &self.config.lines[idx];
+        (self.with_comments && matches!(line.kind, LineKind::Comment))
+            .then(|| line.match_text.as_deref().unwrap_or_default())
+    }
+
+    /// Leaves become `set` lines with their full path; `inactive:` and
+    /// `protect:` become `deactivate` and `protect` lines after the subtree.
+    fn set(&self, idx: usize, out: &mut Vec<EmittedLine>) {
+        if let Some(text) = self.comment(idx) {
+            out.push(EmittedLine {
+                idx,
+                kind: LineKind::Comment,
+                text: format!("# {text}"),
+            });
+            return;
+        }
+        let line = &self.config.lines[idx];
+        if !matches!(line.kind, LineKind::Command) {
+            return;
+        }
+
+        let path = line_path(self.config, idx).join(" ");
+        if !self.is_block(idx) {
+            out.push(EmittedLine {
+                idx,
+                kind: LineKind::Command,
+                text: format!("set {path}"),
+            });
+        }
+        for child in self.children(idx) {
+            self.set(child, out);
+        }
+        for (flag, verb) in [
+            (NodeFlags::INACTIVE, "deactivate"),
+            (NodeFlags::PROTECT, "protect"),
+        ] {
+            if line.flags.contains(flag) {
+                out.push(EmittedLine {
+                    idx,
+                    kind: LineKind::Closing,
+                    text: format!("{verb} {path}"),
+                });
+            }
+        }
+    }
+
+    /// Statements nest under their parents as the brace parser indents them.
+    /// Deleted statements are left out, along with blocks that only held
+    /// deleted statements.
+    fn brace(&self, idx: usize, depth: usize, out: &mut Vec<EmittedLine>) {
+        let indent = " ".repeat(depth * BRACE_INDENT);
+        if let Some(text) = self.comment(idx) {
+            out.push(EmittedLine {
+                idx,
+                kind: LineKind::Comment,
+                text: format!("{indent}/* {text} */"),
+            });
+            return;
+        }
+        let line = &self.config.lines[idx];
+        if !matches!(line.kind, LineKind::Command) || line.flags.contains(NodeFlags::DELETED) {
+            return;
+        }
+
+        let mut prefix = String::new();
+        for (flag, name) in [
+            (NodeFlags::INACTIVE, "inactive: "),
+            (NodeFlags::PROTECT, "protect: "),
+        ] {
+            if line.flags.contains(flag) {
+                prefix.push_str(name);
+            }
+        }
+        let text = line.match_text.as_deref().unwrap_or_default();
+
+        if !self.is_block(idx) {
+            out.push(EmittedLine {
+                idx,
+                kind: LineKind::Command,
+                text: format!("{indent}{prefix}{text};"),
+            });
+            return;
+        }
+
+        let mut body = Vec::new();
+        for child in self.children(idx) {
+            self.brace(child, depth + 1, &mut body);
+        }
+        if !body
+            .iter()
+            .any(|line| matches!(line.kind, LineKind::Command))
+        {
+            return;
+        }
+        out.push(EmittedLine {
+            idx,
+            kind: LineKind::Command,
+            text: format!("{indent}{prefix}{text} {{"),
+        });
+        out.append(&mut body);
+        out.push(EmittedLine {
+            idx,
+            kind: LineKind::Closing,
+            text: format!("{indent}}}"),
+        });
+    }
+}
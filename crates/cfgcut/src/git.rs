@@ -0,0 +1,228 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use gix::ObjectId;
+use gix::bstr::ByteSlice;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+
+use crate::CfgcutError;
+use crate::walk::{IGNORE_FILE, InputFilter, glob_base, is_hidden};
+
+/// A file in the tree of a revision, located but not yet read.
+pub(crate) struct GitFile {
+    /// The input path the file was found through, extended with its path
+    /// below that input.
+    pub path: PathBuf,
+    repo: Arc<gix::ThreadSafeRepository>,
+    id: ObjectId,
+}
+
+impl GitFile {
+    pub(crate) fn read(&self, revision: &str) -> Result<Vec<u8>, CfgcutError> {
+        self.repo
+            .to_thread_local()
+            .find_object(self.id)
+            .map(|object| object.detach().data)
+            .map_err(|err| error(&self.path, revision, err))
+    }
+}
+
+/// Find the files `input` names in the tree of `revision`, in the repository
+/// that contains it. The input is resolved against the repository root
+/// without touching the working tree, so it may name files that have since
+/// been deleted, and a glob pattern is matched against the paths in the tree.
+/// Trees are walked like directories on disk, skipping hidden entries, paths
+/// listed in the [`IGNORE_FILE`]s of the tree and its parents, and files
+/// `filter` rejects.
+pub(crate) fn collect(
+    input: &Path,
+    pattern: Option<&glob::Pattern>,
+    revision: &str,
+    filter: &InputFilter,
+) -> Result<Vec<GitFile>, CfgcutError> {
+    // Start from the part of the input that needs no expansion.
+    let base = match pattern {
+        Some(_) => glob_base(input),
+        None => input.to_path_buf(),
+    };
+    let fail = |message: &dyn std::fmt::Display| error(input, revision, message);
+
+    // The input may not exist on disk at all, so the repository is found
+    // from its closest existing ancestor.
+    let mut existing = base.as_path();
+    let mut missing = Vec::new();
+    while !existing.as_os_str().is_empty() && !existing.exists() {
+        missing.extend(existing.file_name());
+        existing = existing.parent().unwrap_or_else(|| Path::new(""));
+    }
+    if existing.as_os_str().is_empty() {
+        existing = Path::new(".");
+    }
+    let existing = existing.canonicalize().map_err(|err| fail(&err))?;
+    let start = if existing.is_dir() {
+        existing.as_path()
+    } else {
+        existing.parent().unwrap_or(&existing)
+    };
+    let repo = gix::discover(start).map_err(|err| fail(&err))?;
+
+    let root = repo
+        .workdir()
+        .unwrap_or_else(|| repo.git_dir())
+        .canonicalize()
+        .map_err(|err| fail(&err))?;
+    let mut relative = existing
+        .strip_prefix(&root)
+        .map_err(|_| fail(&"path is outside the repository"))?
+        .to_path_buf();
+    relative.extend(missing.iter().rev());
+
+    let tree = repo
+        .rev_parse_single(revision)
+        .map_err(|err| fail(&err))?
+        .object()
+        .map_err(|err| fail(&err))?
+        .peel_to_tree()
+        .map_err(|err| fail(&err))?;
+
+    let mut found = Vec::new();
+    let mut walked = true;
+    if relative.as_os_str().is_empty() {
+        walk(&tree, &relative, &base, &mut Vec::new(), &mut found).map_err(|err| fail(&err))?;
+    } else {
+        let entry = tree
+            .lookup_entry_by_path(&relative)
+            .map_err(|err| fail(&err))?
+            .ok_or_else(|| fail(&"no such path at this revision"))?;
+        if entry.mode().is_tree() {
+            let subtree = entry
+                .object()
+                .map_err(|err| fail(&err))?
+                .peel_to_tree()
+                .map_err(|err| fail(&err))?;
+            let mut ignores = parent_ignores(&tree, &relative).map_err(|err| fail(&err))?;
+            walk(&subtree, &relative, &base, &mut ignores, &mut found).map_err(|err| fail(&err))?;
+        } else if entry.mode().is_blob() {
+            walked = false;
+            found.push((base.clone(), entry.id().detach()));
+        }
+    }
+
+    if walked {
+        found.retain(|(path, _)| filter.allows(path.strip_prefix(&base).unwrap_or(path)));
+    }
+    if let Some(pattern) = pattern {
+        let options = glob::MatchOptions {
+            require_literal_separator: true,
+            ..glob::MatchOptions::new()
+        };
+        found.retain(|(path, _)| pattern.matches_path_with(path, options));
+    }
+
+    drop(tree);
+    let repo = Arc::new(repo.into_sync());
+    Ok(found
+        .into_iter()
+        .map(|(path, id)| GitFile {
+            path,
+            repo: Arc::clone(&repo),
+            id,
+        })
+        .collect())
+}
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Collect every blob below `tree`, found at `dir` in the repository and
+/// reported below `prefix`, skipping hidden entries, symbolic links,
+/// submodules, and paths the ignore files in `ignores` or in the tree list.
+fn walk(
+    tree: &gix::Tree<'_>,
+    dir: &Path,
+    prefix: &Path,
+    ignores: &mut Vec<Gitignore>,
+    found: &mut Vec<(PathBuf, ObjectId)>,
+) -> Result<(), BoxError> {
+    let own = read_ignore(tree, dir)?;
+    let pushed = own.is_some();
+    ignores.extend(own);
+    for entry in tree.iter() {
+        let entry = entry?;
+        let name = entry.filename().to_str_lossy();
+        if is_hidden(&name) {
+            continue;
+        }
+        let relative = dir.join(name.as_ref());
+        if is_ignored(ignores, &relative, entry.mode().is_tree()) {
+            continue;
+        }
+        let path = prefix.join(name.as_ref());
+        if entry.mode().is_tree() {
+            walk(
+                &entry.object()?.peel_to_tree()?,
+                &relative,
+                &path,
+                ignores,
+                found,
+            )?;
+        } else if entry.mode().is_blob() {
+            found.push((path, entry.id().detach()));
+        }
+    }
+    if pushed {
+        ignores.pop();
+    }
+    Ok(())
+}
+
+/// The ignore files of the directories above `relative`, outermost first,
+/// as a walk from the repository root would have collected them.
+fn parent_ignores(root: &gix::Tree<'_>, relative: &Path) -> Result<Vec<Gitignore>, BoxError> {
+    let mut ignores = Vec::new();
+    let mut dir = PathBuf::new();
+    ignores.extend(read_ignore(root, &dir)?);
+    for component in relative.parent().into_iter().flat_map(Path::components) {
+        dir.push(component);
+        if let Some(entry) = root.lookup_entry_by_path(&dir)?
+            && entry.mode().is_tree()
+        {
+            ignores.extend(read_ignore(&entry.object()?.peel_to_tree()?, &dir)?);
+        }
+    }
+    Ok(ignores)
+}
+
+/// Read the ignore file of `tree`, found at `dir` in the repository.
+fn read_ignore(tree: &gix::Tree<'_>, dir: &Path) -> Result<Option<Gitignore>, BoxError> {
+    let Some(entry) = tree.find_entry(IGNORE_FILE) else {
+        return Ok(None);
+    };
+    if !entry.mode().is_blob() {
+        return Ok(None);
+    }
+    let data = entry.object()?.detach().data;
+    let mut builder = GitignoreBuilder::new(dir);
+    for line in String::from_utf8_lossy(&data).lines() {
+        builder.add_line(None, line)?;
+    }
+    Ok(Some(builder.build()?))
+}
+
+/// Whether the innermost ignore file with a rule for `relative` ignores it,
+/// so a `!` rule in a nested file can bring back what a parent ignored.
+fn is_ignored(ignores: &[Gitignore], relative: &Path, is_dir: bool) -> bool {
+    ignores
+        .iter()
+        .rev()
+        .map(|ignore| ignore.matched(relative, is_dir))
+        .find(|matched| !matched.is_none())
+        .is_some_and(|matched| matched.is_ignore())
+}
+
+fn error(path: &Path, revision: &str, message: impl std::fmt::Display) -> CfgcutError {
+    CfgcutError::Git {
+        path: path.to_path_buf(),
+        revision: revision.to_string(),
+        message: message.to_string(),
+    }
+}
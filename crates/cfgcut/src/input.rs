@@ -0,0 +1,103 @@
+use std::fmt;
+use std::io::{self, Read};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// The label given to standard input when the caller does not name it.
+pub const STDIN_LABEL: &str = "stdin";
+
+/// A configuration source to scan.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum InputSource {
+    /// A file, a directory scanned recursively, or a glob pattern.
+    Path(PathBuf),
+    /// Configuration text held in memory.
+    Text {
+        /// The name used in headings, warnings, and token records.
+        label: String,
+        /// The configuration text.
+        text: String,
+    },
+    /// Configuration read from a stream when the run starts.
+    Reader {
+        /// The name used in headings, warnings, and token records.
+        label: String,
+        /// The stream to read.
+        reader: SharedReader,
+    },
+}
+
+impl InputSource {
+    /// A file, directory, or glob pattern.
+    #[must_use]
+    pub fn path(path: impl Into<PathBuf>) -> Self {
+        Self::Path(path.into())
+    }
+
+    /// Configuration text labelled `label`.
+    #[must_use]
+    pub fn text(label: impl Into<String>, text: impl Into<String>) -> Self {
+        Self::Text {
+            label: label.into(),
+            text: text.into(),
+        }
+    }
+
+    /// Configuration read from `reader`, labelled `label`.
+    #[must_use]
+    pub fn reader(label: impl Into<String>, reader: impl Read + Send + 'static) -> Self {
+        Self::Reader {
+            label: label.into(),
+            reader: SharedReader::new(reader),
+        }
+    }
+
+    /// Configuration read from standard input, labelled `label`.
+    #[must_use]
+    pub fn stdin(label: impl Into<String>) -> Self {
+        Self::reader(label, io::stdin())
+    }
+}
+
+impl From<PathBuf> for InputSource {
+    fn from(path: PathBuf) -> Self {
+        Self::Path(path)
+    }
+}
+
+/// A stream that can sit in a cloneable [`RunRequest`](crate::RunRequest).
+///
+/// # Limitations
+///
+/// Clones share the stream, and a stream can only be read once: the first
+/// run that scans it reads it to the end, and a request reused for another
+/// run fails on it. Use [`InputSource::text`] for input that is scanned more
+/// than once.
+#[derive(Clone)]
+pub struct SharedReader(Arc<Mutex<Option<Box<dyn Read + Send>>>>);
+
+impl SharedReader {
+    /// Wrap `reader` so it can be shared.
+    #[must_use]
+    pub fn new(reader: impl Read + Send + 'static) -> Self {
+        Self(Arc::new(Mutex::new(Some(Box::new(reader)))))
+    }
+
+    /// Read the whole stream, which can only happen once.
+    pub(crate) fn read_to_end(&self) -> io::Result<Vec<u8>> {
+        let reader = self.0.lock().unwrap_or_else(PoisonError::into_inner).take();
+        let Some(mut reader) = reader else {
+            return Err(io::Error::other("input stream was already read"));
+        };
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        Ok(bytes)
+    }
+}
+
+impl fmt::Debug for SharedReader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SharedReader")
+    }
+}
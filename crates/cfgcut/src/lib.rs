@@ -4,23 +4,39 @@
 //! single invocation.
 
 use std::borrow::Cow;
-use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque};
 use std::fmt;
 use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
 
+use encoding_rs::Encoding;
 use glob::{PatternError, glob};
+use rayon::prelude::*;
 use regex::Regex;
 use serde::Serialize;
 
 mod anonymize;
+mod archive;
+mod capture;
+mod decode;
 mod dialect;
+mod emit;
+mod git;
 mod inline_match;
-
-use self::dialect::{DialectKind, LineKind, ParsedConfig};
-use anonymize::{Anonymizer, TokenCapture, collect_plain_tokens};
+mod input;
+mod walk;
+
+pub use self::dialect::{DialectKind, UnknownDialect};
+use self::dialect::{Layout, LineKind, NodeFlags, ParsedConfig};
+pub use self::emit::{EmitSyntax, UnknownEmitSyntax};
+pub use self::input::{InputSource, STDIN_LABEL, SharedReader};
+use anonymize::{Anonymizer, collect_plain_tokens};
+use archive::Member;
+use decode::Decoded;
+use emit::EmittedLine;
 use inline_match::{InlineMatchParse, parse_inline_matches};
+use walk::InputFilter;
 
 /// Errors that can be returned while executing the cfgcut pipeline.
 #[derive(Debug)]
@@ -51,7 +67,7 @@ pub enum CfgcutError {
         /// The underlying regular-expression error.
         source: regex::Error,
     },
-    /// No input paths were provided to the run request.
+    /// No inputs were provided to the run request.
     NoInputPaths,
     /// A glob pattern could not be parsed.
     GlobPatternInvalid {
@@ -82,6 +98,39 @@ pub enum CfgcutError {
     },
     /// The requested token destination is not supported.
     UnsupportedTokenDestination,
+    /// The device delimiter could not be compiled.
+    DeviceDelimiter {
+        /// The delimiter supplied by the caller.
+        pattern: String,
+        /// The underlying regular-expression error.
+        source: regex::Error,
+    },
+    /// An `--include` or `--exclude` glob could not be parsed.
+    FilterPattern {
+        /// The glob supplied by the caller.
+        pattern: String,
+        /// The underlying glob parser error.
+        source: globset::Error,
+    },
+    /// The encoding forced for every input is not recognised.
+    UnknownEncoding {
+        /// The encoding label supplied by the caller.
+        label: String,
+    },
+    /// An input could not be read from a git revision.
+    Git {
+        /// The input path as given.
+        path: PathBuf,
+        /// The revision it was read at.
+        revision: String,
+        /// A human-readable error message.
+        message: String,
+    },
+    /// The worker threads for `--jobs` could not be started.
+    ThreadPool {
+        /// The underlying thread-pool error.
+        source: rayon::ThreadPoolBuildError,
+    },
     /// Serializing structured output failed.
     Serialization {
         /// The underlying serialization error.
@@ -118,7 +167,7 @@ impl fmt::Display for CfgcutError {
                     source
                 )
             }
-            Self::NoInputPaths => f.write_str("no input paths provided"),
+            Self::NoInputPaths => f.write_str("no inputs provided"),
             Self::GlobPatternInvalid { pattern, source } => {
                 write!(f, "invalid glob pattern '{pattern}': {source}")
             }
@@ -137,6 +186,25 @@ impl fmt::Display for CfgcutError {
                 write!(f, "scoped projection requires {missing}")
             }
             Self::UnsupportedTokenDestination => f.write_str("unsupported token destination"),
+            Self::DeviceDelimiter { pattern, source } => {
+                write!(f, "invalid device delimiter '{pattern}': {source}")
+            }
+            Self::FilterPattern { pattern, source } => {
+                write!(f, "invalid filter pattern '{pattern}': {source}")
+            }
+            Self::UnknownEncoding { label } => write!(f, "unknown encoding '{label}'"),
+            Self::Git {
+                path,
+                revision,
+                message,
+            } => write!(
+                f,
+                "failed to read '{}' at revision '{revision}': {message}",
+                path.display()
+            ),
+            Self::ThreadPool { source } => {
+                write!(f, "failed to start worker threads: {source}")
+            }
             Self::Serialization { source } => {
                 write!(f, "failed to serialize token record: {source}")
             }
@@ -149,10 +217,16 @@ impl std::error::Error for CfgcutError {
         match self {
             Self::Io { source, .. } => Some(source),
             Self::Pattern(_, err) => Some(err),
-            Self::InlinePattern { source, .. } => Some(source),
+            Self::InlinePattern { source, .. } | Self::DeviceDelimiter { source, .. } => {
+                Some(source)
+            }
             Self::GlobPatternInvalid { source, .. } => Some(source),
+            Self::FilterPattern { source, .. } => Some(source),
+            Self::ThreadPool { source } => Some(source),
             Self::Serialization { source } => Some(source),
             Self::InlineMatches { .. }
+            | Self::Git { .. }
+            | Self::UnknownEncoding { .. }
             | Self::NoInputPaths
             | Self::GlobPatternNoMatches { .. }
             | Self::NoPatternsProvided { .. }
@@ -179,8 +253,17 @@ pub struct RunRequest {
     output_mode: OutputMode,
     render_order: RenderOrder,
     anonymization: Anonymization,
-    inputs: Vec<PathBuf>,
+    inputs: Vec<InputSource>,
     token_output: Option<TokenDestination>,
+    dialect: Option<DialectKind>,
+    emit_syntax: EmitSyntax,
+    device_delimiter: Option<String>,
+    capture_handling: CaptureHandling,
+    git_revision: Option<String>,
+    include: Vec<String>,
+    exclude: Vec<String>,
+    encoding: Option<String>,
+    jobs: usize,
 }
 
 /// Controls whether comments are included in the rendered output.
@@ -216,6 +299,17 @@ impl Default for RenderOrder {
     }
 }
 
+/// Whether terminal-session artefacts are removed from inputs before parsing.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CaptureHandling {
+    /// Strip prompts, pager prompts, escape sequences, and the banners `show`
+    /// commands print around a configuration.
+    #[default]
+    Clean,
+    /// Parse inputs exactly as written.
+    Raw,
+}
+
 /// Whether anonymization of sensitive tokens is enabled.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Anonymization {
@@ -268,6 +362,8 @@ impl TokenKind {
 pub struct TokenRecord {
     /// The dialect of configuration from which the token originated.
     pub dialect: DialectKind,
+    /// The input the token was found in, labelled as in the output heading.
+    pub source: String,
     /// The hierarchical path to the matched command.
     pub path: Vec<String>,
     /// The type of token that was captured.
@@ -276,7 +372,8 @@ pub struct TokenRecord {
     pub original: String,
     /// The anonymized replacement, if anonymization was enabled.
     pub anonymized: Option<String>,
-    /// The line number in the source file.
+    /// The line number in the source file, or 0 when the statement does not
+    /// appear there as written.
     pub line: usize,
 }
 
@@ -324,9 +421,9 @@ impl RunRequest {
         &self.requirements
     }
 
-    /// The input paths gathered for this run.
+    /// The inputs gathered for this run.
     #[must_use]
-    pub fn inputs(&self) -> &[PathBuf] {
+    pub fn inputs(&self) -> &[InputSource] {
         &self.inputs
     }
 
@@ -353,6 +450,62 @@ impl RunRequest {
     pub const fn anonymization(&self) -> Anonymization {
         self.anonymization
     }
+
+    /// The dialect forced for every input, bypassing detection, if configured.
+    #[must_use]
+    pub const fn dialect(&self) -> Option<DialectKind> {
+        self.dialect
+    }
+
+    /// The syntax Junos matches are printed in.
+    #[must_use]
+    pub const fn emit_syntax(&self) -> EmitSyntax {
+        self.emit_syntax
+    }
+
+    /// The regular expression marking the start of each device in an input,
+    /// if configured.
+    #[must_use]
+    pub fn device_delimiter(&self) -> Option<&str> {
+        self.device_delimiter.as_deref()
+    }
+
+    /// Whether terminal-session artefacts are stripped before parsing.
+    #[must_use]
+    pub const fn capture_handling(&self) -> CaptureHandling {
+        self.capture_handling
+    }
+
+    /// The git revision path inputs are read at, if configured.
+    #[must_use]
+    pub fn git_revision(&self) -> Option<&str> {
+        self.git_revision.as_deref()
+    }
+
+    /// Globs a file found by expanding a directory or pattern must match.
+    #[must_use]
+    pub fn include(&self) -> &[String] {
+        &self.include
+    }
+
+    /// Globs that skip files found by expanding a directory or pattern.
+    #[must_use]
+    pub fn exclude(&self) -> &[String] {
+        &self.exclude
+    }
+
+    /// The encoding label forced for every input, bypassing detection, if
+    /// configured.
+    #[must_use]
+    pub fn encoding(&self) -> Option<&str> {
+        self.encoding.as_deref()
+    }
+
+    /// How many inputs are processed at once, with `0` meaning one per CPU.
+    #[must_use]
+    pub const fn jobs(&self) -> usize {
+        self.jobs
+    }
 }
 
 /// Builder for [`RunRequest`].
@@ -365,8 +518,17 @@ pub struct RunRequestBuilder {
     output_mode: OutputMode,
     render_order: RenderOrder,
     anonymization: Anonymization,
-    inputs: Vec<PathBuf>,
+    inputs: Vec<InputSource>,
     token_output: Option<TokenDestination>,
+    dialect: Option<DialectKind>,
+    emit_syntax: EmitSyntax,
+    device_delimiter: Option<String>,
+    capture_handling: CaptureHandling,
+    git_revision: Option<String>,
+    include: Vec<String>,
+    exclude: Vec<String>,
+    encoding: Option<String>,
+    jobs: usize,
 }
 
 impl Default for RunRequestBuilder {
@@ -381,6 +543,15 @@ impl Default for RunRequestBuilder {
             anonymization: Anonymization::Disabled,
             inputs: Vec::new(),
             token_output: None,
+            dialect: None,
+            emit_syntax: EmitSyntax::Source,
+            device_delimiter: None,
+            capture_handling: CaptureHandling::Clean,
+            git_revision: None,
+            include: Vec::new(),
+            exclude: Vec::new(),
+            encoding: None,
+            jobs: 1,
         }
     }
 }
@@ -435,10 +606,25 @@ impl RunRequestBuilder {
         self
     }
 
-    /// Provide the inputs that cfgcut should scan.
+    /// Provide the files, directories, and glob patterns that cfgcut should
+    /// scan, replacing any inputs added so far.
     #[must_use]
     pub fn inputs(mut self, inputs: Vec<PathBuf>) -> Self {
-        self.inputs = inputs;
+        self.inputs = inputs.into_iter().map(InputSource::Path).collect();
+        self
+    }
+
+    /// Provide the inputs that cfgcut should scan, replacing any added so far.
+    #[must_use]
+    pub fn sources(mut self, sources: Vec<InputSource>) -> Self {
+        self.inputs = sources;
+        self
+    }
+
+    /// Add one input to scan, such as in-memory text or standard input.
+    #[must_use]
+    pub fn source(mut self, source: InputSource) -> Self {
+        self.inputs.push(source);
         self
     }
 
@@ -449,6 +635,77 @@ impl RunRequestBuilder {
         self
     }
 
+    /// Force a dialect for every input instead of detecting it per file.
+    #[must_use]
+    pub const fn dialect(mut self, dialect: Option<DialectKind>) -> Self {
+        self.dialect = dialect;
+        self
+    }
+
+    /// Print Junos matches as `set` statements or as a brace hierarchy,
+    /// whichever syntax the source was written in.
+    #[must_use]
+    pub const fn emit_syntax(mut self, syntax: EmitSyntax) -> Self {
+        self.emit_syntax = syntax;
+        self
+    }
+
+    /// Split every input into one configuration per device at lines matching
+    /// this regular expression.
+    #[must_use]
+    pub fn device_delimiter(mut self, delimiter: Option<String>) -> Self {
+        self.device_delimiter = delimiter;
+        self
+    }
+
+    /// Configure whether terminal-session artefacts are stripped before
+    /// parsing.
+    #[must_use]
+    pub const fn capture_handling(mut self, handling: CaptureHandling) -> Self {
+        self.capture_handling = handling;
+        self
+    }
+
+    /// Read path inputs from the tree of this revision in the git repository
+    /// that contains them, instead of from the working tree.
+    #[must_use]
+    pub fn git_revision(mut self, revision: Option<String>) -> Self {
+        self.git_revision = revision;
+        self
+    }
+
+    /// Only scan files found in directories and glob patterns that match one
+    /// of these globs. Files named directly are always scanned.
+    #[must_use]
+    pub fn include(mut self, globs: Vec<String>) -> Self {
+        self.include = globs;
+        self
+    }
+
+    /// Skip files found in directories and glob patterns that match any of
+    /// these globs.
+    #[must_use]
+    pub fn exclude(mut self, globs: Vec<String>) -> Self {
+        self.exclude = globs;
+        self
+    }
+
+    /// Decode every input with the encoding of this label, such as
+    /// `utf-16le` or `latin1`, instead of detecting it per file.
+    #[must_use]
+    pub fn encoding(mut self, label: Option<String>) -> Self {
+        self.encoding = label;
+        self
+    }
+
+    /// Process up to `jobs` inputs at once; `0` uses one thread per CPU.
+    /// Output is the same as with a single job.
+    #[must_use]
+    pub const fn jobs(mut self, jobs: usize) -> Self {
+        self.jobs = jobs;
+        self
+    }
+
     /// Finalize the builder and produce a [`RunRequest`].
     #[must_use]
     pub fn build(self) -> RunRequest {
@@ -462,6 +719,15 @@ impl RunRequestBuilder {
             anonymization: self.anonymization,
             inputs: self.inputs,
             token_output: self.token_output,
+            dialect: self.dialect,
+            emit_syntax: self.emit_syntax,
+            device_delimiter: self.device_delimiter,
+            capture_handling: self.capture_handling,
+            git_revision: self.git_revision,
+            include: self.include,
+            exclude: self.exclude,
+            encoding: self.encoding,
+            jobs: self.jobs,
         }
     }
 }
@@ -522,29 +788,296 @@ fn compile_patterns(patterns: &[String]) -> Result<Vec<Pattern>, CfgcutError> {
     Ok(compiled)
 }
 
-struct ParsedFile {
+/// The contents of one input file, split into the configurations it holds.
+struct SourceFile {
     inline_matches: Option<Vec<String>>,
+    documents: Vec<Document>,
+    /// Whether the file ends its lines with CRLF, which output repeats.
+    crlf: bool,
+}
+
+/// One device configuration within an input.
+struct Document {
+    /// The device named by the delimiter line, when the input was split.
+    device: Option<String>,
+    text: String,
+    /// The 1-based line of the input file each line of `text` came from.
+    lines: Vec<usize>,
+}
+
+struct ParsedDocument {
     parsed: ParsedConfig,
     dialect_kind: DialectKind,
+    detection_warning: Option<String>,
 }
 
-fn parse_config_file(path: &Path) -> Result<ParsedFile, CfgcutError> {
-    let raw_content = fs::read_to_string(path).map_err(|source| CfgcutError::Io {
-        path: path.to_path_buf(),
-        source,
-    })?;
+/// An input after paths have been expanded into the files they name.
+enum ResolvedInput<'a> {
+    File(PathBuf),
+    Git {
+        file: git::GitFile,
+        revision: &'a str,
+    },
+    Text {
+        label: &'a str,
+        text: &'a str,
+    },
+    Reader {
+        label: &'a str,
+        reader: &'a SharedReader,
+    },
+    /// Contents read and decompressed while expanding an archive or a stream.
+    Unpacked {
+        path: PathBuf,
+        label: String,
+        bytes: Vec<u8>,
+    },
+}
+
+impl ResolvedInput<'_> {
+    /// The path named in errors and warnings: the file itself, or the label
+    /// of an in-memory input.
+    fn path(&self) -> PathBuf {
+        match self {
+            Self::File(path) => path.clone(),
+            Self::Git { file, revision } => {
+                PathBuf::from(format!("{}@{revision}", file.path.display()))
+            }
+            Self::Text { label, .. } | Self::Reader { label, .. } => PathBuf::from(label),
+            Self::Unpacked { path, .. } => path.clone(),
+        }
+    }
+
+    /// The name used in headings and token records.
+    fn label(&self) -> String {
+        match self {
+            Self::File(path) => file_label(path),
+            Self::Git { file, revision } => format!("{}@{revision}", file_label(&file.path)),
+            Self::Text { label, .. } | Self::Reader { label, .. } => (*label).to_string(),
+            Self::Unpacked { label, .. } => label.clone(),
+        }
+    }
+
+    /// Expand a tar archive, compressed or not, into one input per member
+    /// `filter` allows, named `archive:member` and sorted by member path, so
+    /// members are scheduled like separate files. Streams are read here, as
+    /// they can only be read once; other inputs are left to [`Self::read`].
+    fn expand(self, filter: &InputFilter) -> Result<Vec<Self>, CfgcutError> {
+        let path = self.path();
+        let allows = |name: &str| filter.allows(Path::new(name));
+        let io_error = |source| CfgcutError::Io {
+            path: path.clone(),
+            source,
+        };
+        let members = match &self {
+            Self::Text { .. } | Self::Unpacked { .. } => None,
+            Self::File(file) => fs::File::open(file)
+                .and_then(|file| archive::members(file, allows))
+                .map_err(io_error)?,
+            Self::Git { file, revision } => {
+                archive::members(file.read(revision)?.as_slice(), allows).map_err(io_error)?
+            }
+            Self::Reader { reader, .. } => {
+                let bytes = reader.read_to_end().map_err(io_error)?;
+                match archive::members(bytes.as_slice(), allows).map_err(io_error)? {
+                    Some(members) => Some(members),
+                    None => {
+                        return Ok(vec![Self::Unpacked {
+                            label: self.label(),
+                            bytes: archive::decompress(bytes.as_slice()).map_err(io_error)?,
+                            path,
+                        }]);
+                    }
+                }
+            }
+        };
+        let Some(members) = members else {
+            return Ok(vec![self]);
+        };
+
+        let label = self.label();
+        Ok(members
+            .into_iter()
+            .map(|Member { name, bytes }| Self::Unpacked {
+                path: PathBuf::from(format!("{}:{name}", path.display())),
+                label: format!("{label}:{name}"),
+                bytes,
+            })
+            .collect())
+    }
+
+    /// Read the configuration this input holds, decompressing files. Text is
+    /// decoded as described in [`decode::decode`], and binary files are
+    /// skipped with a warning.
+    fn read(
+        &self,
+        encoding: Option<&'static Encoding>,
+        warnings: &mut Vec<String>,
+    ) -> Result<Option<InputFile<'_>>, CfgcutError> {
+        let path = self.path();
+        let bytes = match self {
+            Self::Text { text, .. } => {
+                return Ok(Some(InputFile {
+                    path,
+                    label: self.label(),
+                    text: Cow::Borrowed(*text),
+                }));
+            }
+            Self::Unpacked { bytes, .. } => Ok(Cow::Borrowed(bytes.as_slice())),
+            Self::File(file) => fs::File::open(file)
+                .and_then(archive::decompress)
+                .map(Cow::Owned),
+            Self::Git { file, revision } => {
+                archive::decompress(file.read(revision)?.as_slice()).map(Cow::Owned)
+            }
+            Self::Reader { reader, .. } => reader
+                .read_to_end()
+                .and_then(|bytes| archive::decompress(bytes.as_slice()))
+                .map(Cow::Owned),
+        }
+        .map_err(|source| CfgcutError::Io {
+            path: path.clone(),
+            source,
+        })?;
+
+        match decode::decode(&bytes, encoding) {
+            Decoded::Text(text, note) => {
+                if let Some(note) = note {
+                    warnings.push(format!("warning: {}: {note}", path.display()));
+                }
+                Ok(Some(InputFile {
+                    path,
+                    label: self.label(),
+                    text: Cow::Owned(text),
+                }))
+            }
+            Decoded::Binary => {
+                warnings.push(format!(
+                    "warning: skipping {}: binary file (use --encoding if it is text)",
+                    path.display()
+                ));
+                Ok(None)
+            }
+        }
+    }
+}
+
+/// One configuration file read from an input.
+struct InputFile<'a> {
+    /// The path named in errors and warnings.
+    path: PathBuf,
+    /// The name used in headings and token records.
+    label: String,
+    text: Cow<'a, str>,
+}
+
+fn read_config_file(
+    file: &InputFile<'_>,
+    delimiter: Option<&Regex>,
+) -> Result<SourceFile, CfgcutError> {
+    // Parsers only see LF endings; output restores CRLF for such files.
+    let crlf = file.text.contains("\r\n");
+    let text = if crlf {
+        Cow::Owned(file.text.replace("\r\n", "\n"))
+    } else {
+        Cow::Borrowed(file.text.as_ref())
+    };
     let InlineMatchParse { matches, body } =
-        parse_inline_matches(&raw_content).map_err(|err| CfgcutError::InlineMatches {
-            path: path.to_path_buf(),
+        parse_inline_matches(&text).map_err(|err| CfgcutError::InlineMatches {
+            path: file.path.clone(),
             message: err.to_string(),
         })?;
-    let content = body.into_owned();
-    let (dialect_kind, parsed) = dialect::parse_with_detect(&content);
-    Ok(ParsedFile {
+    // The inline block is removed along with its line breaks.
+    let first_line = 1 + text.matches('\n').count() - body.matches('\n').count();
+    Ok(SourceFile {
         inline_matches: matches,
+        documents: split_documents(&body, first_line, delimiter),
+        crlf,
+    })
+}
+
+/// Split `text` at every line matching `delimiter`, dropping the delimiter
+/// lines. Each document is named by the delimiter's `device` capture group,
+/// its first group, or failing both the delimiter line itself. Text before
+/// the first delimiter stays an unnamed document unless it is blank, so a
+/// device whose banner is missing is still scanned; an input with no
+/// delimiter lines stays a single document.
+fn split_documents(text: &str, first_line: usize, delimiter: Option<&Regex>) -> Vec<Document> {
+    let mut documents = vec![Document {
+        device: None,
+        text: String::new(),
+        lines: Vec::new(),
+    }];
+    for (offset, line) in text.split_inclusive('\n').enumerate() {
+        let trimmed = line.trim_end();
+        match delimiter.and_then(|delimiter| delimiter.captures(trimmed)) {
+            Some(captures) => {
+                let name = captures
+                    .name("device")
+                    .or_else(|| captures.get(1))
+                    .map_or(trimmed, |name| name.as_str());
+                documents.push(Document {
+                    device: Some(name.trim().to_string()),
+                    text: String::new(),
+                    lines: Vec::new(),
+                });
+            }
+            None => {
+                if let Some(current) = documents.last_mut() {
+                    current.text.push_str(line);
+                    current.lines.push(first_line + offset);
+                }
+            }
+        }
+    }
+    if documents.len() > 1 && documents[0].text.trim().is_empty() {
+        documents.remove(0);
+    }
+    documents
+}
+
+/// Detect and parse one document, first stripping capture artefacts when
+/// `clean` is set. Source line numbers are only looked up when `locate` is
+/// set, since nothing but token records reports them.
+fn parse_document(
+    document: &mut Document,
+    display: &str,
+    dialect_override: Option<DialectKind>,
+    clean: bool,
+    locate: bool,
+) -> ParsedDocument {
+    let captured = clean && capture::strip_session(document);
+    let (dialect_kind, detection_warning) = match dialect_override {
+        Some(kind) => (kind, None),
+        None => {
+            let detection = dialect::detect(&document.text);
+            let warning = detection
+                .runner_up
+                .filter(|_| detection.is_ambiguous())
+                .map(|(runner_kind, runner_score)| {
+                    format!(
+                        "warning: {display}: ambiguous dialect detection, chose {} (score {}) over {} (score {}); use --dialect to override",
+                        detection.kind,
+                        detection.score,
+                        runner_kind,
+                        runner_score
+                    )
+                });
+            (detection.kind, warning)
+        }
+    };
+    if clean {
+        capture::strip_banners(document, dialect_kind, captured);
+    }
+    let mut parsed = dialect::parse_as(dialect_kind, &document.text);
+    if locate {
+        parsed.locate_lines(&document.text);
+    }
+    ParsedDocument {
         parsed,
         dialect_kind,
-    })
+        detection_warning,
+    }
 }
 
 fn resolve_patterns<'a>(
@@ -610,112 +1143,341 @@ fn compile_inline_patterns(
 /// compile, when glob arguments are invalid, or when outputs cannot be
 /// serialized.
 pub fn run(request: &RunRequest) -> Result<RunOutput, CfgcutError> {
-    let files = collect_files(&request.inputs)?;
-    let cli_patterns = compile_cli_patterns(&request.matches)?;
-    let scoped_patterns = compile_scoped_patterns(request)?;
-    let include_comments = matches!(request.comment_handling, CommentHandling::Include);
-    let anonymize = matches!(request.anonymization, Anonymization::Enabled);
-
-    let mut output = String::new();
-    let mut matched_any = false;
-    let mut anonymizer = anonymize.then(Anonymizer::new);
-    let mut tokens = Vec::new();
-    let mut warnings = Vec::new();
-
-    for path in files {
-        let ParsedFile {
-            inline_matches,
+    let filter = InputFilter::new(&request.include, &request.exclude)?;
+    let encoding = request
+        .encoding
+        .as_deref()
+        .map(|label| {
+            decode::encoding_for_label(label).ok_or_else(|| CfgcutError::UnknownEncoding {
+                label: label.to_string(),
+            })
+        })
+        .transpose()?;
+    let files = collect_files(&request.inputs, request.git_revision.as_deref(), &filter)?;
+    let context = RunContext {
+        request,
+        cli_patterns: compile_cli_patterns(&request.matches)?,
+        scoped_patterns: compile_scoped_patterns(request)?,
+        delimiter: compile_device_delimiter(request.device_delimiter.as_deref())?,
+        filter,
+        encoding,
+    };
+
+    let mut merged = MergedOutput::new(request);
+    if request.jobs == 1 {
+        for input in files {
+            for input in input.expand(&context.filter)? {
+                merged.push(process_input(&context, &input)?);
+            }
+        }
+    } else {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(request.jobs)
+            .build()
+            .map_err(|source| CfgcutError::ThreadPool { source })?;
+        // Archives are expanded first so their members are spread over the
+        // pool like any other file.
+        let outcomes = pool.install(|| {
+            let expanded = files
+                .into_par_iter()
+                .map(|input| input.expand(&context.filter))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok::<_, CfgcutError>(
+                expanded
+                    .into_par_iter()
+                    .flatten()
+                    .map(|input| process_input(&context, &input))
+                    .collect::<Vec<_>>(),
+            )
+        })?;
+        for outcome in outcomes {
+            merged.push(outcome?);
+        }
+    }
+
+    Ok(merged.finish())
+}
+
+/// Everything about a run that is shared by its inputs, compiled once.
+struct RunContext<'a> {
+    request: &'a RunRequest,
+    cli_patterns: Option<Vec<Pattern>>,
+    scoped_patterns: Option<ScopedPatterns>,
+    delimiter: Option<Regex>,
+    filter: InputFilter,
+    encoding: Option<&'static Encoding>,
+}
+
+/// What one input produced. Inputs are processed independently, possibly in
+/// parallel, and merged in input order by [`MergedOutput`].
+#[derive(Default)]
+struct InputOutcome {
+    matched: bool,
+    warnings: Vec<String>,
+    sections: Vec<Section>,
+}
+
+/// The matched lines of one document, not yet anonymized.
+struct Section {
+    /// The `cfgcut matches for` heading, without its line ending. JSON has
+    /// no comments, so JSON sections go without one to keep stdout a stream
+    /// of JSON documents.
+    heading: Option<String>,
+    newline: &'static str,
+    dialect: DialectKind,
+    /// The label token records carry.
+    source: String,
+    lines: Vec<PendingLine>,
+    layout: Layout,
+    /// Whether any parsed line was selected, which is what an XML envelope
+    /// is printed for even when no line is left to show.
+    selected: bool,
+}
+
+/// A line of output before anonymization.
+struct PendingLine {
+    text: String,
+    kind: LineKind,
+    /// Whether a JSON member separator follows the line.
+    separator: bool,
+    /// Where the line came from, for token records, when they are wanted.
+    origin: Option<TokenOrigin>,
+}
+
+struct TokenOrigin {
+    path: Vec<String>,
+    line: usize,
+}
+
+/// Collects input outcomes in input order. Lines are anonymized here rather
+/// than while inputs are processed, so the anonymizer numbers values in the
+/// order a serial run meets them however inputs are scheduled.
+struct MergedOutput {
+    stdout: String,
+    matched: bool,
+    anonymizer: Option<Anonymizer>,
+    tokens: Option<Vec<TokenRecord>>,
+    warnings: Vec<String>,
+}
+
+impl MergedOutput {
+    fn new(request: &RunRequest) -> Self {
+        Self {
+            stdout: String::new(),
+            matched: false,
+            anonymizer: matches!(request.anonymization, Anonymization::Enabled)
+                .then(Anonymizer::new),
+            tokens: request.token_output.as_ref().map(|_| Vec::new()),
+            warnings: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, outcome: InputOutcome) {
+        self.matched |= outcome.matched;
+        self.warnings.extend(outcome.warnings);
+        for section in outcome.sections {
+            let Some(rendered) = section.render(self.anonymizer.as_mut(), self.tokens.as_mut())
+            else {
+                continue;
+            };
+            if !self.stdout.is_empty() && !self.stdout.ends_with('\n') {
+                self.stdout.push('\n');
+            }
+            self.stdout.push_str(&rendered);
+        }
+    }
+
+    fn finish(self) -> RunOutput {
+        RunOutput {
+            matched: self.matched,
+            stdout: self.stdout,
+            tokens: self.tokens.unwrap_or_default(),
+            warnings: self.warnings,
+        }
+    }
+}
+
+/// Read, parse, and match every configuration one input holds.
+fn process_input(
+    context: &RunContext<'_>,
+    input: &ResolvedInput<'_>,
+) -> Result<InputOutcome, CfgcutError> {
+    let request = context.request;
+    let mut outcome = InputOutcome::default();
+
+    let Some(file) = input.read(context.encoding, &mut outcome.warnings)? else {
+        return Ok(outcome);
+    };
+    let path = &file.path;
+    let file_label = &file.label;
+    let SourceFile {
+        inline_matches,
+        documents,
+        crlf,
+    } = read_config_file(&file, context.delimiter.as_ref())?;
+    let newline = if crlf { "\r\n" } else { "\n" };
+
+    let pattern_set = if context.scoped_patterns.is_some() {
+        if inline_matches.is_some() {
+            outcome.warnings.push(format!(
+                "{}: ignoring inline matches because CLI patterns were provided",
+                path.display()
+            ));
+        }
+        None
+    } else {
+        let (pattern_set, warning) = resolve_patterns(
+            context.cli_patterns.as_deref(),
+            inline_matches.as_deref(),
+            path,
+        )?;
+        outcome.warnings.extend(warning);
+        Some(pattern_set)
+    };
+
+    for mut document in documents {
+        let (display, label) = match &document.device {
+            Some(device) => (
+                format!("{}:{device}", path.display()),
+                format!("{file_label}:{device}"),
+            ),
+            None => (path.display().to_string(), file_label.clone()),
+        };
+        let ParsedDocument {
             parsed,
             dialect_kind,
-        } = parse_config_file(&path)?;
-
-        let mut token_accumulator = request
-            .token_output
-            .as_ref()
-            .map(|_| TokenAccumulator::new(dialect_kind));
+            detection_warning,
+        } = parse_document(
+            &mut document,
+            &display,
+            request.dialect,
+            matches!(request.capture_handling, CaptureHandling::Clean),
+            request.token_output.is_some(),
+        );
+        outcome.warnings.extend(detection_warning);
+        if !request.emit_syntax.applies_to(dialect_kind) {
+            outcome.warnings.push(format!(
+                "{display}: --emit {} only applies to Junos configurations; printing {} as written",
+                request.emit_syntax, dialect_kind
+            ));
+        }
+        let emit_target = request.emit_syntax.target(dialect_kind);
 
         let mut indices = BTreeSet::new();
         let mut matched_file = false;
 
-        if let Some(scoped) = &scoped_patterns {
-            if inline_matches.is_some() {
-                warnings.push(format!(
-                    "{}: ignoring inline matches because CLI patterns were provided",
-                    path.display()
-                ));
-            }
+        if let Some(scoped) = &context.scoped_patterns {
             let accumulator = apply_scoped_patterns(&parsed, scoped);
-            if accumulator.matched {
-                matched_any = true;
-                matched_file = true;
-            }
+            matched_file |= accumulator.matched;
             indices.extend(accumulator.indices);
-        } else {
-            let (pattern_set, warning) =
-                resolve_patterns(cli_patterns.as_deref(), inline_matches.as_deref(), &path)?;
-            if let Some(message) = warning {
-                warnings.push(message);
-            }
-
-            for pattern in pattern_set.iter() {
-                let mut accumulator = MatchAccumulator::new(&parsed);
-                pattern.apply(&parsed, &mut accumulator);
-                if accumulator.matched {
-                    matched_any = true;
-                    matched_file = true;
-                }
-                indices.extend(accumulator.indices);
-            }
         }
-
-        if matched_file {
-            let ordered = order_indices(&parsed, &indices, request.render_order());
-            let rendered = render_output(
-                &parsed,
-                &ordered,
-                include_comments,
-                anonymizer.as_mut(),
-                token_accumulator.as_mut(),
-            );
-            if !rendered.is_empty() {
-                if !output.is_empty() && !output.ends_with('\n') {
-                    output.push('\n');
-                }
-                let label = file_label(&path);
-                output.push_str(comment_marker_for(dialect_kind));
-                output.push_str(" cfgcut matches for ");
-                output.push_str(&label);
-                output.push('\n');
-                output.push_str(&rendered);
-                if !rendered.ends_with('\n') {
-                    output.push('\n');
-                }
-            }
-        } else {
-            warnings.push(format!("warning: no matches found in {}", path.display()));
+        for pattern in pattern_set.iter().flat_map(|patterns| patterns.iter()) {
+            let mut accumulator = MatchAccumulator::new(&parsed);
+            pattern.apply(&parsed, &mut accumulator);
+            matched_file |= accumulator.matched;
+            indices.extend(accumulator.indices);
         }
 
-        if let Some(accumulator) = token_accumulator {
-            tokens.extend(accumulator.finish());
+        if !matched_file {
+            outcome
+                .warnings
+                .push(format!("warning: no matches found in {display}"));
+            continue;
         }
+        outcome.matched = true;
+
+        let ordered = order_indices(&parsed, &indices, request.render_order());
+        let line_numbers = request
+            .token_output
+            .as_ref()
+            .map(|_| document.lines.as_slice());
+        let (lines, selected) = pending_lines(
+            &parsed,
+            &ordered,
+            matches!(request.comment_handling, CommentHandling::Include),
+            emit_target,
+            line_numbers,
+        );
+        let output_dialect = emit_target.unwrap_or(dialect_kind);
+        outcome.sections.push(Section {
+            heading: (!matches!(parsed.layout, Layout::Json { .. })).then(|| {
+                format!(
+                    "{} cfgcut matches for {label}{}",
+                    comment_marker_for(output_dialect),
+                    comment_terminator_for(output_dialect)
+                )
+            }),
+            newline,
+            dialect: dialect_kind,
+            source: label,
+            lines,
+            layout: parsed.layout,
+            selected,
+        });
     }
 
-    Ok(RunOutput {
-        matched: matched_any,
-        stdout: output,
-        tokens,
-        warnings,
+    Ok(outcome)
+}
+
+fn compile_device_delimiter(raw: Option<&str>) -> Result<Option<Regex>, CfgcutError> {
+    raw.map(|pattern| {
+        Regex::new(pattern).map_err(|source| CfgcutError::DeviceDelimiter {
+            pattern: pattern.to_string(),
+            source,
+        })
     })
+    .transpose()
 }
 
-fn collect_files(inputs: &[PathBuf]) -> Result<Vec<PathBuf>, CfgcutError> {
+/// Expand the inputs into the configurations they hold, in the order given.
+/// The files found through consecutive paths are sorted together, and a file
+/// found twice is only scanned the first time. With a revision, paths are
+/// looked up in git.
+fn collect_files<'a>(
+    inputs: &'a [InputSource],
+    revision: Option<&'a str>,
+    filter: &InputFilter,
+) -> Result<Vec<ResolvedInput<'a>>, CfgcutError> {
     if inputs.is_empty() {
         return Err(CfgcutError::NoInputPaths);
     }
 
+    let mut resolved = Vec::new();
+    let mut seen = HashSet::new();
     let mut files = Vec::new();
+    let mut blobs = Vec::new();
     for input in inputs {
+        let buffer = match input {
+            InputSource::Path(_) => None,
+            InputSource::Text { label, text } => Some(ResolvedInput::Text { label, text }),
+            InputSource::Reader { label, reader } => Some(ResolvedInput::Reader { label, reader }),
+        };
+        let InputSource::Path(input) = input else {
+            flush_paths(&mut files, &mut blobs, revision, &mut seen, &mut resolved);
+            resolved.extend(buffer);
+            continue;
+        };
+        if let Some(revision) = revision {
+            let pattern = glob_pattern(input);
+            let compiled = pattern
+                .as_deref()
+                .map(glob::Pattern::new)
+                .transpose()
+                .map_err(|source| CfgcutError::GlobPatternInvalid {
+                    pattern: pattern.clone().unwrap_or_default(),
+                    source,
+                })?;
+            let found = git::collect(input, compiled.as_ref(), revision, filter)?;
+            if let Some(pattern) = pattern
+                && found.is_empty()
+            {
+                return Err(CfgcutError::GlobPatternNoMatches { pattern });
+            }
+            blobs.extend(found);
+            continue;
+        }
         if let Some(pattern) = glob_pattern(input) {
+            let base = walk::glob_base(input);
             let mut matched_any = false;
             let paths = glob(&pattern).map_err(|err| CfgcutError::GlobPatternInvalid {
                 pattern: pattern.clone(),
@@ -736,8 +1498,9 @@ fn collect_files(inputs: &[PathBuf]) -> Result<Vec<PathBuf>, CfgcutError> {
                 };
 
                 if path.is_dir() {
-                    gather_dir(&path, &mut files)?;
-                } else if path.is_file() {
+                    walk::gather_dir(&path, filter, &mut files)?;
+                } else if path.is_file() && filter.allows(path.strip_prefix(&base).unwrap_or(&path))
+                {
                     files.push(path);
                 }
             }
@@ -752,7 +1515,7 @@ fn collect_files(inputs: &[PathBuf]) -> Result<Vec<PathBuf>, CfgcutError> {
         if input.is_file() {
             files.push(input.clone());
         } else if input.is_dir() {
-            gather_dir(input, &mut files)?;
+            walk::gather_dir(input, filter, &mut files)?;
         } else {
             return Err(CfgcutError::Io {
                 path: input.clone(),
@@ -761,16 +1524,69 @@ fn collect_files(inputs: &[PathBuf]) -> Result<Vec<PathBuf>, CfgcutError> {
         }
     }
 
+    flush_paths(&mut files, &mut blobs, revision, &mut seen, &mut resolved);
+    Ok(resolved)
+}
+
+/// Move the files found through a run of consecutive paths to `resolved`,
+/// sorted, and skipping any file already in `seen`.
+fn flush_paths<'a>(
+    files: &mut Vec<PathBuf>,
+    blobs: &mut Vec<git::GitFile>,
+    revision: Option<&'a str>,
+    seen: &mut HashSet<PathBuf>,
+    resolved: &mut Vec<ResolvedInput<'a>>,
+) {
     files.sort();
-    files.dedup();
-    Ok(files)
+    resolved.extend(
+        files
+            .drain(..)
+            .filter(|path| seen.insert(path.clone()))
+            .map(ResolvedInput::File),
+    );
+    blobs.sort_by(|a, b| a.path.cmp(&b.path));
+    resolved.extend(
+        blobs
+            .drain(..)
+            .filter(|file| seen.insert(file.path.clone()))
+            .map(|file| ResolvedInput::Git {
+                file,
+                revision: revision.unwrap_or_default(),
+            }),
+    );
 }
 
 const fn comment_marker_for(dialect: DialectKind) -> &'static str {
     match dialect {
-        DialectKind::CiscoIos | DialectKind::CiscoNxos | DialectKind::AristaEos => "!",
+        DialectKind::CiscoIos
+        | DialectKind::CiscoNxos
+        | DialectKind::CiscoIosXr
+        | DialectKind::AristaEos
+        | DialectKind::HuaweiVrp
+        | DialectKind::ArubaAoscx => "!",
+        DialectKind::HpeProcurve => ";",
+        DialectKind::CiscoAsa => ":",
         DialectKind::JuniperJunos => "##",
-        DialectKind::JuniperJunosSet => "#",
+        DialectKind::JuniperJunosXml => "<!--",
+        DialectKind::JuniperJunosSet
+        | DialectKind::PaloAltoPanos
+        | DialectKind::PaloAltoPanosSet
+        | DialectKind::FortinetFortios
+        | DialectKind::NokiaSros
+        | DialectKind::NokiaSrosMd
+        | DialectKind::MikrotikRouteros
+        | DialectKind::F5BigIp
+        | DialectKind::VyosSet
+        | DialectKind::Yaml => "#",
+        DialectKind::Vyos | DialectKind::SonicConfigDb | DialectKind::OpenconfigJson => "//",
+    }
+}
+
+/// Closes the heading comment for dialects whose comments are delimited.
+const fn comment_terminator_for(dialect: DialectKind) -> &'static str {
+    match dialect {
+        DialectKind::JuniperJunosXml => " -->",
+        _ => "",
     }
 }
 
@@ -790,31 +1606,6 @@ fn glob_pattern(path: &Path) -> Option<String> {
     }
 }
 
-fn gather_dir(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), CfgcutError> {
-    let mut entries = std::fs::read_dir(dir)
-        .map_err(|source| CfgcutError::Io {
-            path: dir.to_path_buf(),
-            source,
-        })?
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(|source| CfgcutError::Io {
-            path: dir.to_path_buf(),
-            source,
-        })?;
-
-    entries.sort_by_key(std::fs::DirEntry::path);
-
-    for entry in entries {
-        let path = entry.path();
-        if path.is_dir() {
-            gather_dir(&path, files)?;
-        } else if path.is_file() {
-            files.push(path);
-        }
-    }
-    Ok(())
-}
-
 #[derive(Debug, Clone)]
 struct Pattern {
     segments: Vec<PatternSegment>,
@@ -906,9 +1697,13 @@ impl Pattern {
                 accumulator.record_full(node_idx);
                 accumulator.matched = true;
             }
-            PatternSegment::Match { regex, target } => {
+            PatternSegment::Match {
+                regex,
+                target,
+                flags,
+            } => {
                 let line = &config.lines[node_idx];
-                if !target.matches(line.kind) {
+                if !target.matches(line.kind) || !line.flags.contains(*flags) {
                     return;
                 }
                 if let Some(candidate) = line.match_text.as_deref() {
@@ -947,9 +1742,13 @@ impl Pattern {
             PatternSegment::DescendAll => {
                 matches.insert(node_idx);
             }
-            PatternSegment::Match { regex, target } => {
+            PatternSegment::Match {
+                regex,
+                target,
+                flags,
+            } => {
                 let line = &config.lines[node_idx];
-                if !target.matches(line.kind) {
+                if !target.matches(line.kind) || !line.flags.contains(*flags) {
                     return;
                 }
                 if let Some(candidate) = line.match_text.as_deref() {
@@ -1021,13 +1820,28 @@ fn apply_scoped_patterns<'a>(
     output
 }
 
+/// Build a segment, reading leading `inactive:`/`protect:` prefixes as a
+/// requirement on the node's flags; a bare prefix matches any such node.
 fn create_segment(
     raw: &str,
     pattern: &str,
     target: MatchTarget,
 ) -> Result<PatternSegment, CfgcutError> {
+    let (flags, pattern) = match target {
+        MatchTarget::Command => NodeFlags::strip_prefixes(pattern.trim()),
+        MatchTarget::Comment => (NodeFlags::default(), pattern),
+    };
+    let pattern = if pattern.trim().is_empty() {
+        ".*"
+    } else {
+        pattern
+    };
     let regex = compile_pattern(raw, pattern)?;
-    Ok(PatternSegment::Match { regex, target })
+    Ok(PatternSegment::Match {
+        regex,
+        target,
+        flags,
+    })
 }
 
 fn compile_pattern(raw: &str, fragment: &str) -> Result<Regex, CfgcutError> {
@@ -1047,7 +1861,11 @@ fn compile_pattern(raw: &str, fragment: &str) -> Result<Regex, CfgcutError> {
 
 #[derive(Debug, Clone)]
 enum PatternSegment {
-    Match { regex: Regex, target: MatchTarget },
+    Match {
+        regex: Regex,
+        target: MatchTarget,
+        flags: NodeFlags,
+    },
     DescendAll,
 }
 
@@ -1061,8 +1879,10 @@ impl MatchTarget {
     const fn matches(self, kind: LineKind) -> bool {
         matches!(
             (self, kind),
-            (Self::Command, LineKind::Command | LineKind::Closing)
-                | (Self::Comment, LineKind::Comment)
+            (
+                Self::Command,
+                LineKind::Command | LineKind::Closing | LineKind::Attribute
+            ) | (Self::Comment, LineKind::Comment)
         )
     }
 }
@@ -1148,40 +1968,25 @@ pub fn fuzz_matcher(pattern: &str, text: &str) {
     }
 }
 
-struct TokenAccumulator {
-    dialect: DialectKind,
-    entries: Vec<TokenRecord>,
-}
-
-impl TokenAccumulator {
-    const fn new(dialect: DialectKind) -> Self {
-        Self {
-            dialect,
-            entries: Vec::new(),
-        }
-    }
-
-    fn record(&mut self, config: &ParsedConfig, idx: usize, captures: &[TokenCapture]) {
-        if captures.is_empty() {
-            return;
-        }
-        let path = line_path(config, idx);
-        let line_no = idx + 1;
-        for capture in captures {
-            self.entries.push(TokenRecord {
-                dialect: self.dialect,
-                path: path.clone(),
-                kind: capture.kind,
-                original: capture.original.clone(),
-                anonymized: capture.anonymized.clone(),
-                line: line_no,
-            });
-        }
+/// The parsed-text line an entry came from. Statements a parser synthesised
+/// take the line of the first statement below them, which is the one that
+/// created them in a `set` tree, or else the line of their parent.
+fn source_line(config: &ParsedConfig, idx: usize) -> Option<usize> {
+    fn first_descendant(config: &ParsedConfig, idx: usize) -> Option<usize> {
+        config.children[idx].iter().find_map(|&child| {
+            config.lines[child]
+                .line
+                .or_else(|| first_descendant(config, child))
+        })
     }
 
-    fn finish(self) -> Vec<TokenRecord> {
-        self.entries
-    }
+    config.lines[idx]
+        .line
+        .or_else(|| first_descendant(config, idx))
+        .or_else(|| {
+            let parent = config.lines[idx].parent?;
+            source_line(config, parent)
+        })
 }
 
 fn line_path(config: &ParsedConfig, idx: usize) -> Vec<String> {
@@ -1243,43 +2048,148 @@ fn root_index(config: &ParsedConfig, mut idx: usize) -> usize {
     idx
 }
 
-fn render_output(
+/// Select the output lines for `ordered`, converted to `emit_target` when
+/// set, along with whether anything was selected at all. `line_numbers`
+/// maps parsed lines to input lines when token records are wanted.
+fn pending_lines(
     config: &ParsedConfig,
     ordered: &[usize],
     with_comments: bool,
-    mut anonymizer: Option<&mut Anonymizer>,
-    mut tokens: Option<&mut TokenAccumulator>,
-) -> String {
-    let mut buf = String::new();
-    for &idx in ordered {
-        let line = &config.lines[idx];
-        if matches!(line.kind, LineKind::Comment) && !with_comments {
-            continue;
+    emit_target: Option<DialectKind>,
+    line_numbers: Option<&[usize]>,
+) -> (Vec<PendingLine>, bool) {
+    let (ordered, separators) = match config.layout {
+        Layout::Lines | Layout::Xml { .. } => (Cow::Borrowed(ordered), HashSet::new()),
+        Layout::Json { .. } => {
+            let expanded = dialect::json::with_list_keys(config, ordered);
+            let separators = dialect::json::separators(config, &expanded);
+            (Cow::Owned(expanded), separators)
         }
+    };
+    let lines = match emit_target {
+        Some(target) => emit::convert(config, &ordered, target, with_comments),
+        None => ordered
+            .iter()
+            .map(|&idx| EmittedLine {
+                idx,
+                kind: config.lines[idx].kind,
+                text: config.lines[idx].raw.clone(),
+            })
+            .collect(),
+    };
+    let pending = lines
+        .into_iter()
+        .filter(|line| {
+            !(matches!(line.kind, LineKind::Attribute)
+                || (matches!(line.kind, LineKind::Comment) && !with_comments))
+        })
+        .map(|EmittedLine { idx, kind, text }| PendingLine {
+            separator: separators.contains(&idx),
+            origin: line_numbers
+                .filter(|_| matches!(kind, LineKind::Command))
+                .map(|numbers| TokenOrigin {
+                    path: line_path(config, idx),
+                    line: source_line(config, idx)
+                        .and_then(|line| numbers.get(line - 1))
+                        .copied()
+                        .unwrap_or_default(),
+                }),
+            text,
+            kind,
+        })
+        .collect();
+    (pending, !ordered.is_empty())
+}
 
-        let mut captures = Vec::new();
-        let text = match (anonymizer.as_mut(), tokens.as_ref(), line.kind) {
-            (Some(tool), Some(_), LineKind::Command) => {
-                tool.scrub_with_tokens(&line.raw, &mut captures)
+impl Section {
+    /// Anonymize the lines and print the section under its heading, or
+    /// return `None` when nothing is left to print. Token records are
+    /// appended to `tokens` when it is given.
+    fn render(
+        self,
+        mut anonymizer: Option<&mut Anonymizer>,
+        mut tokens: Option<&mut Vec<TokenRecord>>,
+    ) -> Option<String> {
+        let mut buf = String::new();
+        for PendingLine {
+            text,
+            kind,
+            separator,
+            origin,
+        } in self.lines
+        {
+            let mut captures = Vec::new();
+            let text = match (anonymizer.as_mut(), tokens.is_some(), kind) {
+                (Some(tool), true, LineKind::Command) => {
+                    tool.scrub_with_tokens(&text, &mut captures)
+                }
+                (Some(tool), _, _) => tool.scrub(&text),
+                (None, true, LineKind::Command) => {
+                    captures = collect_plain_tokens(&text);
+                    text
+                }
+                (None, _, _) => text,
+            };
+
+            if let (Some(tokens), Some(origin)) = (tokens.as_deref_mut(), origin) {
+                tokens.extend(captures.into_iter().map(|capture| TokenRecord {
+                    dialect: self.dialect,
+                    source: self.source.clone(),
+                    path: origin.path.clone(),
+                    kind: capture.kind,
+                    original: capture.original,
+                    anonymized: capture.anonymized,
+                    line: origin.line,
+                }));
             }
-            (Some(tool), _, _) => tool.scrub(&line.raw),
-            (None, Some(_), LineKind::Command) => {
-                captures = collect_plain_tokens(&line.raw);
-                line.raw.clone()
+
+            buf.push_str(&text);
+            if separator {
+                buf.push(',');
             }
-            (None, _, _) => line.raw.clone(),
-        };
+            buf.push('\n');
+        }
 
-        if let Some(tokens) = tokens.as_deref_mut()
-            && !captures.is_empty()
-        {
-            tokens.record(config, idx, &captures);
+        let body = match self.layout {
+            Layout::Json { root_array } if !buf.is_empty() => {
+                let (open, close) = if root_array { ('[', ']') } else { ('{', '}') };
+                format!("{open}\n{buf}{close}\n")
+            }
+            // A match on a `<configuration>` attribute selects the envelope alone.
+            Layout::Xml { envelope } if self.selected => {
+                let mut wrapped = String::new();
+                for (open, _) in &envelope {
+                    wrapped.push_str(open);
+                    wrapped.push('\n');
+                }
+                wrapped.push_str(&buf);
+                for (_, close) in envelope.iter().rev() {
+                    wrapped.push_str(close);
+                    wrapped.push('\n');
+                }
+                wrapped
+            }
+            _ => buf,
+        };
+        if body.is_empty() {
+            return None;
         }
 
-        buf.push_str(&text);
-        buf.push('\n');
+        let mut section = String::new();
+        if let Some(heading) = self.heading {
+            section.push_str(&heading);
+            section.push_str(self.newline);
+        }
+        if self.newline == "\n" {
+            section.push_str(&body);
+        } else {
+            section.push_str(&body.replace('\n', self.newline));
+        }
+        if !body.ends_with('\n') {
+            section.push_str(self.newline);
+        }
+        Some(section)
     }
-    buf
 }
 
 #[cfg(test)]
@@ -1313,4 +2223,73 @@ mod tests {
         pattern.apply(&config, &mut accumulator);
         assert!(accumulator.matched);
     }
+
+    #[test]
+    fn split_documents_names_devices_from_the_delimiter() {
+        let text = "\n### edge1\nhostname edge1\n### edge2\nhostname edge2\n";
+        let named = Regex::new(r"^### (?<device>\S+)$").unwrap();
+        let documents = split_documents(text, 1, Some(&named));
+        let summary = documents
+            .iter()
+            .map(|doc| (doc.device.as_deref(), doc.text.as_str(), doc.lines.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            vec![
+                (Some("edge1"), "hostname edge1\n", vec![3]),
+                (Some("edge2"), "hostname edge2\n", vec![5]),
+            ]
+        );
+
+        let headless = "hostname edge0\n### edge1\nhostname edge1\n";
+        let documents = split_documents(headless, 1, Some(&named));
+        let summary = documents
+            .iter()
+            .map(|doc| (doc.device.as_deref(), doc.text.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            vec![
+                (None, "hostname edge0\n"),
+                (Some("edge1"), "hostname edge1\n")
+            ]
+        );
+
+        let bare = Regex::new(r"^### ").unwrap();
+        let documents = split_documents(text, 1, Some(&bare));
+        assert_eq!(documents[0].device.as_deref(), Some("### edge1"));
+
+        let absent = Regex::new(r"^=== ").unwrap();
+        let documents = split_documents(text, 1, Some(&absent));
+        assert_eq!(documents.len(), 1);
+        assert_eq!(documents[0].device, None);
+        assert_eq!(documents[0].text, text);
+    }
+
+    #[test]
+    fn in_memory_sources_keep_their_place_under_their_labels() {
+        let fixture = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../../tests/fixtures/cisco_ios/out_of_order.conf");
+        let request = RunRequest::builder()
+            .matches(vec!["hostname .*".to_string()])
+            .source(InputSource::reader("edge2", "hostname edge2\n".as_bytes()))
+            .source(InputSource::path(&fixture))
+            .source(InputSource::text("edge1", "hostname edge1\n"))
+            .source(InputSource::path(fixture))
+            .build();
+        let output = run(&request).unwrap();
+        let headings = output
+            .stdout
+            .lines()
+            .filter(|line| line.starts_with("! cfgcut"))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            headings,
+            vec![
+                "! cfgcut matches for edge2",
+                "! cfgcut matches for out_of_order.conf",
+                "! cfgcut matches for edge1",
+            ]
+        );
+    }
 }
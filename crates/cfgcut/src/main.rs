@@ -5,8 +5,9 @@ use std::path::PathBuf;
 use clap::{ArgAction, Parser};
 
 use cfgcut::{
-    Anonymization, CfgcutError, CommentHandling, OutputMode, RenderOrder, RunRequest,
-    TokenDestination, TokenRecord, run,
+    Anonymization, CaptureHandling, CfgcutError, CommentHandling, DialectKind, EmitSyntax,
+    InputSource, OutputMode, RenderOrder, RunRequest, STDIN_LABEL, TokenDestination, TokenRecord,
+    run,
 };
 
 #[derive(Parser, Debug)]
@@ -63,7 +64,51 @@ struct Cli {
     #[arg(long = "tokens-out")]
     tokens_out: Option<PathBuf>,
 
-    /// Input configuration files or directories
+    /// Force the configuration dialect instead of detecting it per file
+    #[arg(long = "dialect", value_name = "DIALECT")]
+    dialect: Option<DialectKind>,
+
+    /// Print Junos matches as `set` statements or as a brace hierarchy
+    #[arg(long = "emit", value_name = "SYNTAX")]
+    emit: Option<EmitSyntax>,
+
+    /// Split inputs into one configuration per device at lines matching this regex
+    #[arg(long = "device-delimiter", value_name = "REGEX")]
+    device_delimiter: Option<String>,
+
+    /// Parse inputs as written, keeping prompts, pager prompts, and show banners
+    #[arg(long = "raw", conflicts_with = "clean_capture")]
+    raw: bool,
+
+    /// Strip terminal-session artefacts from captured output (the default)
+    #[arg(long = "clean-capture")]
+    clean_capture: bool,
+
+    /// Only scan files in directories and glob matches that match this glob
+    #[arg(long = "include", action = ArgAction::Append, value_name = "GLOB")]
+    include: Vec<String>,
+
+    /// Skip files in directories and glob matches that match this glob
+    #[arg(long = "exclude", action = ArgAction::Append, value_name = "GLOB")]
+    exclude: Vec<String>,
+
+    /// Decode every input with this encoding (e.g. utf-16le, latin1) instead of detecting it
+    #[arg(long = "encoding", value_name = "ENCODING")]
+    encoding: Option<String>,
+
+    /// Read path inputs from this revision of the git repository that contains them
+    #[arg(long = "git-rev", value_name = "REV")]
+    git_rev: Option<String>,
+
+    /// Process up to N inputs in parallel (0 uses every CPU); output is unchanged
+    #[arg(short = 'j', long = "jobs", value_name = "N", default_value_t = 1)]
+    jobs: usize,
+
+    /// Name standard input is reported under in headings and token records
+    #[arg(long = "stdin-label", value_name = "NAME", default_value = STDIN_LABEL)]
+    stdin_label: String,
+
+    /// Input configuration files or directories, or '-' to read standard input
     #[arg(value_name = "PATH", required = true)]
     inputs: Vec<PathBuf>,
 }
@@ -81,9 +126,33 @@ fn main() {
         anonymize,
         tokens,
         tokens_out,
+        dialect,
+        emit,
+        device_delimiter,
+        raw,
+        clean_capture: _,
+        include,
+        exclude,
+        encoding,
+        git_rev,
+        jobs,
+        stdin_label,
         inputs,
     } = cli;
 
+    let mut reads_stdin = false;
+    let sources = inputs
+        .into_iter()
+        .filter_map(|input| {
+            if input.as_os_str() != "-" {
+                return Some(InputSource::Path(input));
+            }
+            // Standard input can only be read once, however often it is named.
+            (!std::mem::replace(&mut reads_stdin, true))
+                .then(|| InputSource::stdin(stdin_label.as_str()))
+        })
+        .collect();
+
     let request = RunRequest::builder()
         .matches(matches)
         .within(within)
@@ -108,12 +177,25 @@ fn main() {
         } else {
             Anonymization::Disabled
         })
-        .inputs(inputs)
+        .sources(sources)
         .token_output(
             tokens_out
                 .map(TokenDestination::File)
                 .or_else(|| tokens.then_some(TokenDestination::Stdout)),
         )
+        .dialect(dialect)
+        .emit_syntax(emit.unwrap_or_default())
+        .device_delimiter(device_delimiter)
+        .capture_handling(if raw {
+            CaptureHandling::Raw
+        } else {
+            CaptureHandling::Clean
+        })
+        .git_revision(git_rev)
+        .include(include)
+        .exclude(exclude)
+        .encoding(encoding)
+        .jobs(jobs)
         .build();
 
     match run(&request) {
@@ -0,0 +1,141 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ignore::WalkBuilder;
+
+use crate::CfgcutError;
+
+/// Ignore files read while walking directories, with gitignore semantics.
+pub(crate) const IGNORE_FILE: &str = ".cfgcutignore";
+
+/// The `--include` and `--exclude` globs applied to files found by expanding
+/// directories and glob patterns. As in a gitignore file, a glob without a
+/// `/` matches the file name and one with a `/` matches the path below the
+/// directory being walked.
+#[derive(Default)]
+pub(crate) struct InputFilter {
+    include: Option<Globs>,
+    exclude: Option<Globs>,
+}
+
+struct Globs {
+    names: GlobSet,
+    paths: GlobSet,
+}
+
+impl InputFilter {
+    pub(crate) fn new(include: &[String], exclude: &[String]) -> Result<Self, CfgcutError> {
+        Ok(Self {
+            include: Globs::new(include)?,
+            exclude: Globs::new(exclude)?,
+        })
+    }
+
+    /// Whether a file at `relative` below the walked directory is scanned.
+    pub(crate) fn allows(&self, relative: &Path) -> bool {
+        self.include
+            .as_ref()
+            .is_none_or(|include| include.matches(relative))
+            && !self
+                .exclude
+                .as_ref()
+                .is_some_and(|exclude| exclude.matches(relative))
+    }
+}
+
+impl Globs {
+    fn new(patterns: &[String]) -> Result<Option<Self>, CfgcutError> {
+        if patterns.is_empty() {
+            return Ok(None);
+        }
+        let mut names = GlobSetBuilder::new();
+        let mut paths = GlobSetBuilder::new();
+        for pattern in patterns {
+            let anchored = pattern.trim_start_matches('/');
+            let glob = Glob::new(anchored).map_err(|source| CfgcutError::FilterPattern {
+                pattern: pattern.clone(),
+                source,
+            })?;
+            if pattern.contains('/') {
+                paths.add(glob);
+            } else {
+                names.add(glob);
+            }
+        }
+        let build = |builder: GlobSetBuilder| {
+            builder
+                .build()
+                .map_err(|source| CfgcutError::FilterPattern {
+                    pattern: patterns.join(", "),
+                    source,
+                })
+        };
+        Ok(Some(Self {
+            names: build(names)?,
+            paths: build(paths)?,
+        }))
+    }
+
+    fn matches(&self, relative: &Path) -> bool {
+        relative
+            .file_name()
+            .is_some_and(|name| self.names.is_match(name))
+            || self.paths.is_match(relative)
+    }
+}
+
+/// Whether a file or directory name marks it hidden, like `.git` or an
+/// editor swap file.
+pub(crate) fn is_hidden(name: &str) -> bool {
+    name.starts_with('.')
+}
+
+/// Whether a path component holds glob syntax.
+pub(crate) fn is_glob(text: &str) -> bool {
+    text.contains(['*', '?', '['])
+}
+
+/// The leading components of a glob pattern that hold no glob syntax: the
+/// directory the pattern is expanded in, which `--include` and `--exclude`
+/// paths are relative to.
+pub(crate) fn glob_base(pattern: &Path) -> PathBuf {
+    pattern
+        .components()
+        .take_while(|component| !is_glob(&component.as_os_str().to_string_lossy()))
+        .collect()
+}
+
+/// Collect the files below `dir` in name order, skipping hidden entries,
+/// paths listed in [`IGNORE_FILE`]s, and files `filter` rejects.
+pub(crate) fn gather_dir(
+    dir: &Path,
+    filter: &InputFilter,
+    files: &mut Vec<PathBuf>,
+) -> Result<(), CfgcutError> {
+    let walker = WalkBuilder::new(dir)
+        .standard_filters(false)
+        .hidden(true)
+        .parents(true)
+        .follow_links(true)
+        .add_custom_ignore_filename(IGNORE_FILE)
+        .sort_by_file_name(Ord::cmp)
+        .build();
+    for entry in walker {
+        let entry = entry.map_err(|err| CfgcutError::Io {
+            path: dir.to_path_buf(),
+            source: err.io_error().map_or_else(
+                || io::Error::other(err.to_string()),
+                |source| io::Error::new(source.kind(), source.to_string()),
+            ),
+        })?;
+        if !entry.file_type().is_some_and(|kind| kind.is_file()) {
+            continue;
+        }
+        let relative = entry.path().strip_prefix(dir).unwrap_or(entry.path());
+        if filter.allows(relative) {
+            files.push(entry.into_path());
+        }
+    }
+    Ok(())
+}
@@ -123,3 +123,35 @@ fn junos_set_subtree_matches_expected() {
         .success()
         .stdout(predicate::str::diff(expected));
 }
+
+#[test]
+fn iosxr_route_policy_branch_matches_expected() {
+    let path = fixture_path("cisco_iosxr/sample.conf");
+    let body = "route-policy RP-CUSTOMERS-IN\n  if destination in PFX-CUSTOMERS then\n    set local-preference 200\n  endif\nend-policy\n";
+    let expected = expected_with_header("!", &path, body);
+    cfgcut_cmd()
+        .args([
+            "-m",
+            "route-policy RP-CUSTOMERS-IN||if .*||set local-preference .*",
+            &path,
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::diff(expected));
+}
+
+#[test]
+fn fortios_quoted_value_matches_expected() {
+    let path = fixture_path("fortinet_fortios/sample.conf");
+    let body = "config system interface\n    edit \"wan1\"\n        set description \"WAN uplink to ISP\"\n    next\nend\n";
+    let expected = expected_with_header("#", &path, body);
+    cfgcut_cmd()
+        .args([
+            "-m",
+            "config system interface||edit wan1||set description WAN uplink.*",
+            &path,
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::diff(expected));
+}
@@ -445,3 +445,964 @@ fn junos_unit_descend_returns_full_subtree() {
         .success()
         .stdout(predicate::str::diff(expected));
 }
+
+#[test]
+fn dialect_override_bypasses_detection() {
+    let tmp = tempdir().unwrap();
+    let path = tmp.path().join("edge.set");
+    fs::write(
+        &path,
+        "! exported by backup tool\nset system host-name edge1\nset system ntp server 192.0.2.1\n",
+    )
+    .unwrap();
+    let path_str = path.to_string_lossy().into_owned();
+
+    let mut detected = cfgcut_cmd();
+    detected
+        .args(["-m", "system||host-name edge1", path_str.as_str()])
+        .assert()
+        .failure();
+
+    let mut forced = cfgcut_cmd();
+    forced
+        .args([
+            "--dialect",
+            "juniper-junos-set",
+            "-m",
+            "system||host-name edge1",
+            path_str.as_str(),
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(header("#", &path)))
+        .stdout(predicate::str::contains("set system host-name edge1"));
+}
+
+#[test]
+fn ambiguous_detection_is_reported_as_a_warning() {
+    let tmp = tempdir().unwrap();
+    let path = tmp.path().join("edge.cfg");
+    fs::write(
+        &path,
+        "feature bgp\ninterface Ethernet1/1\n  description x\nmanagement api http-commands\n no shutdown\n",
+    )
+    .unwrap();
+    let path_str = path.to_string_lossy().into_owned();
+
+    cfgcut_cmd()
+        .args(["-m", "interface .*", path_str.as_str()])
+        .assert()
+        .success()
+        .stderr(predicate::str::diff(format!(
+            "warning: {path_str}: ambiguous dialect detection, chose arista-eos (score 25) over cisco-nxos (score 20); use --dialect to override\n"
+        )));
+}
+
+#[test]
+fn unknown_dialect_is_rejected() {
+    let path = fixture_path("cisco_ios/sample.conf");
+    let path_str = path.to_string_lossy().into_owned();
+    let mut cmd = cfgcut_cmd();
+    cmd.args(["--dialect", "bogus", "-m", ".*", path_str.as_str()])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown dialect 'bogus'"));
+}
+
+#[test]
+fn asa_anonymize_scrubs_passwd_and_pre_shared_keys() {
+    let path = fixture_path("cisco_asa/sample.cfg");
+    let path_str = path.to_string_lossy().into_owned();
+    let mut cmd = cfgcut_cmd();
+    cmd.args([
+        "-a",
+        "-c",
+        "-m",
+        "|#|Saved",
+        "-m",
+        "passwd .*",
+        "-m",
+        "tunnel-group .* ipsec-attributes|>>|",
+        path_str.as_str(),
+    ])
+    .assert()
+    .success()
+    .stdout(predicate::str::contains(header(":", &path)))
+    .stdout(predicate::str::contains(": Saved"))
+    .stdout(predicate::str::contains("passwd scrambled1 encrypted"))
+    .stdout(predicate::str::contains(
+        " ikev2 remote-authentication pre-shared-key scrambled2",
+    ))
+    .stdout(predicate::str::contains("Lab-Psk-1").not());
+}
+
+#[test]
+fn json_matches_are_valid_json_with_ancestors() {
+    let path = fixture_path("sonic_config_db/config_db.json");
+    let path_str = path.to_string_lossy().into_owned();
+    let mut cmd = cfgcut_cmd();
+    let assert = cmd
+        .args([
+            "-m",
+            "PORT||.*||admin_status||down",
+            "-m",
+            "BGP_NEIGHBOR|>>|",
+            path_str.as_str(),
+        ])
+        .assert()
+        .success();
+    let value: serde_json::Value = serde_json::from_slice(&assert.get_output().stdout).unwrap();
+    assert_eq!(
+        value,
+        serde_json::json!({
+            "PORT": {"Ethernet4": {"admin_status": "down"}},
+            "BGP_NEIGHBOR": {
+                "10.0.0.57": {"asn": "64600", "name": "spine1", "local_addr": "10.0.0.56"}
+            }
+        })
+    );
+}
+
+#[test]
+fn json_output_for_several_files_is_a_json_stream() {
+    let sonic = fixture_path("sonic_config_db/config_db.json");
+    let openconfig = fixture_path("openconfig_json/interfaces.json");
+    let output = cfgcut_cmd()
+        .args(["-m", ".*"])
+        .arg(&sonic)
+        .arg(&openconfig)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let documents = serde_json::Deserializer::from_slice(&output.stdout)
+        .into_iter::<serde_json::Value>()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(documents.len(), 2);
+    assert!(documents.iter().all(serde_json::Value::is_object));
+}
+
+#[test]
+fn junos_xml_matches_are_well_formed_with_envelope() {
+    let path = fixture_path("juniper_junos_xml/display_xml.xml");
+    let path_str = path.to_string_lossy().into_owned();
+    let mut cmd = cfgcut_cmd();
+    let assert = cmd
+        .args([
+            "-m",
+            "system||login|>>|",
+            "-m",
+            "interfaces||interface ge-0/0/1||inactive=inactive",
+            path_str.as_str(),
+        ])
+        .assert()
+        .success();
+    let stdout = String::from_utf8(assert.get_output().stdout.clone()).unwrap();
+    let (heading, body) = stdout.split_once('\n').unwrap();
+    assert_eq!(
+        heading,
+        format!(
+            "<!-- cfgcut matches for {} -->",
+            path.file_name().unwrap().to_string_lossy()
+        )
+    );
+
+    let mut reader = quick_xml::Reader::from_str(body);
+    let mut elements = Vec::new();
+    loop {
+        match reader.read_event().expect("well-formed XML") {
+            quick_xml::events::Event::Start(start) | quick_xml::events::Event::Empty(start) => {
+                elements.push(String::from_utf8(start.name().as_ref().to_vec()).unwrap());
+            }
+            quick_xml::events::Event::Eof => break,
+            _ => {}
+        }
+    }
+    assert_eq!(
+        elements,
+        [
+            "rpc-reply",
+            "configuration",
+            "system",
+            "login",
+            "message",
+            "interfaces",
+            "interface",
+            "name"
+        ]
+    );
+}
+
+#[test]
+fn emit_set_flattens_brace_matches() {
+    let path = fixture_path("juniper_junos/annotated.conf");
+    let path_str = path.to_string_lossy().into_owned();
+    let expected = format!(
+        "{}\n{}",
+        header("#", &path),
+        "\
+set interfaces ge-0/0/0 description \"uplink {core}\"
+set interfaces ge-0/0/0 mtu 9192
+set interfaces ge-0/0/1 description \"spare\"
+set interfaces ge-0/0/1 disable
+deactivate interfaces ge-0/0/1 disable
+deactivate interfaces ge-0/0/1
+"
+    );
+    let mut cmd = cfgcut_cmd();
+    cmd.args(["--emit", "set", "-m", "interfaces|>>|", path_str.as_str()])
+        .assert()
+        .success()
+        .stdout(predicate::str::diff(expected));
+}
+
+#[test]
+fn emit_brace_nests_set_matches() {
+    let path = fixture_path("juniper_junos_set/change_script.set");
+    let path_str = path.to_string_lossy().into_owned();
+    let expected = format!(
+        "{}\n{}",
+        header("##", &path),
+        "\
+interfaces {
+  ge-0/0/0 {
+    inactive: description \"uplink to core-1\";
+    unit 0 {
+      family inet {
+        address 192.0.2.1/31;
+      }
+    }
+  }
+  inactive: ge-0/0/1 {
+    description spare;
+    disable;
+  }
+}
+"
+    );
+    let mut cmd = cfgcut_cmd();
+    cmd.args(["--emit", "brace", "-m", "interfaces|>>|", path_str.as_str()])
+        .assert()
+        .success()
+        .stdout(predicate::str::diff(expected));
+}
+
+#[test]
+fn emit_round_trips_junos_brace_through_set() {
+    let brace = fixture_str("juniper_junos/full_lab.conf");
+    let source = cfgcut_cmd()
+        .args(["-m", "interfaces|>>|", brace.as_str()])
+        .output()
+        .unwrap();
+    let set = cfgcut_cmd()
+        .args(["--emit", "set", "-m", "interfaces|>>|", brace.as_str()])
+        .output()
+        .unwrap();
+
+    let tmp = tempdir().unwrap();
+    let set_path = tmp.path().join("full_lab.set");
+    fs::write(&set_path, &set.stdout).unwrap();
+    let set_str = set_path.to_string_lossy().into_owned();
+    let round_trip = cfgcut_cmd()
+        .args(["--emit", "brace", "-m", "interfaces|>>|", set_str.as_str()])
+        .output()
+        .unwrap();
+
+    let body = |output: &[u8]| {
+        String::from_utf8_lossy(output)
+            .lines()
+            .skip(1)
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    assert_eq!(body(&round_trip.stdout), body(&source.stdout));
+}
+
+#[test]
+fn emit_leaves_other_dialects_as_written() {
+    let path = fixture_path("cisco_ios/sample.conf");
+    let path_str = path.to_string_lossy().into_owned();
+    let mut cmd = cfgcut_cmd();
+    cmd.args(["--emit", "set", "-m", "interface .*", path_str.as_str()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(header("!", &path)))
+        .stdout(predicate::str::contains("interface GigabitEthernet1"))
+        .stderr(predicate::str::contains(
+            "--emit set only applies to Junos configurations",
+        ));
+}
+
+#[test]
+fn device_delimiter_splits_and_labels_each_device() {
+    let path = fixture_path("multi_device/collection.txt");
+    let path_str = path.to_string_lossy().into_owned();
+    let expected = "\
+! cfgcut matches for collection.txt:edge1
+interface GigabitEthernet1
+ description uplink to core1
+! cfgcut matches for collection.txt:edge2
+interface GigabitEthernet1
+ description uplink to core2
+## cfgcut matches for collection.txt:fw1
+interfaces {
+  ge-0/0/0 {
+    description \"uplink to core1\";
+  }
+}
+";
+    let mut cmd = cfgcut_cmd();
+    cmd.args([
+        "--device-delimiter",
+        r"^===== hostname: (?<device>\S+) =====$",
+        "-m",
+        "interface .*||description .*",
+        "-m",
+        "interfaces||.*||description .*",
+        path_str.as_str(),
+    ])
+    .assert()
+    .success()
+    .stdout(predicate::str::diff(expected))
+    // The collector line above the first banner is scanned on its own.
+    .stderr(format!("warning: no matches found in {}\n", path.display()));
+}
+
+#[test]
+fn device_delimiter_keeps_a_device_whose_banner_is_missing() {
+    let tmp = tempdir().unwrap();
+    let path = tmp.path().join("dump.txt");
+    fs::write(
+        &path,
+        "hostname edge0\n=== edge1 ===\nhostname edge1\n=== edge2 ===\nhostname edge2\n",
+    )
+    .unwrap();
+    cfgcut_cmd()
+        .args([
+            "--device-delimiter",
+            "^=== (\\S+) ===$",
+            "-m",
+            "hostname .*",
+        ])
+        .arg(&path)
+        .assert()
+        .success()
+        .stdout(
+            "! cfgcut matches for dump.txt\nhostname edge0\n\
+             ! cfgcut matches for dump.txt:edge1\nhostname edge1\n\
+             ! cfgcut matches for dump.txt:edge2\nhostname edge2\n",
+        );
+}
+
+#[test]
+fn device_delimiter_labels_token_records() {
+    let path = fixture_path("multi_device/collection.txt");
+    let path_str = path.to_string_lossy().into_owned();
+    let output = cfgcut_cmd()
+        .args([
+            "--device-delimiter",
+            r"^===== hostname: (\S+) =====$",
+            "--tokens",
+            "-m",
+            "router bgp .*",
+            path_str.as_str(),
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let records = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .filter(|line| line.starts_with('{'))
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+        .collect::<Vec<_>>();
+    let sources = records
+        .iter()
+        .map(|record| {
+            (
+                record["source"].as_str().unwrap().to_string(),
+                record["original"].as_str().unwrap().to_string(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        sources,
+        vec![
+            ("collection.txt:edge1".to_string(), "65010".to_string()),
+            ("collection.txt:edge2".to_string(), "65011".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn invalid_device_delimiter_is_rejected() {
+    let path = fixture_path("multi_device/collection.txt");
+    let path_str = path.to_string_lossy().into_owned();
+    let mut cmd = cfgcut_cmd();
+    cmd.args(["--device-delimiter", "(", "-m", ".*", path_str.as_str()])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid device delimiter '('"));
+}
+
+#[test]
+fn terminal_capture_tokens_report_capture_lines() {
+    let path = fixture_path("cisco_ios/terminal_capture.log");
+    let path_str = path.to_string_lossy().into_owned();
+    let output = cfgcut_cmd()
+        .args(["--tokens", "-m", "router bgp .*|>>|", path_str.as_str()])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines = stdout
+        .lines()
+        .filter(|line| line.starts_with('{'))
+        .map(|line| {
+            let record: serde_json::Value = serde_json::from_str(line).unwrap();
+            (
+                record["original"].as_str().unwrap().to_string(),
+                record["line"].as_u64().unwrap(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        lines,
+        vec![
+            ("65010".to_string(), 23),
+            ("65000".to_string(), 24),
+            ("192.0.2.2".to_string(), 24),
+        ]
+    );
+}
+
+#[test]
+fn raw_keeps_capture_artefacts() {
+    let path = fixture_path("cisco_ios/terminal_capture.log");
+    let path_str = path.to_string_lossy().into_owned();
+    let mut cmd = cfgcut_cmd();
+    cmd.args([
+        "--raw",
+        "-m",
+        "Building configuration.*",
+        "-m",
+        "end",
+        path_str.as_str(),
+    ])
+    .assert()
+    .success()
+    .stdout(predicate::str::contains("Building configuration..."))
+    .stdout(predicate::str::contains("\nend\n"));
+
+    let mut cleaned = cfgcut_cmd();
+    cleaned
+        .args([
+            "-m",
+            "Building configuration.*",
+            "-m",
+            "end",
+            path_str.as_str(),
+        ])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn dash_reads_stdin_under_the_stdin_label() {
+    let mut cmd = cfgcut_cmd();
+    cmd.args(["-m", "hostname .*", "-"])
+        .write_stdin("hostname edge1\n")
+        .assert()
+        .success()
+        .stdout("! cfgcut matches for stdin\nhostname edge1\n");
+
+    let output = cfgcut_cmd()
+        .args([
+            "--tokens",
+            "--stdin-label",
+            "edge1",
+            "-m",
+            "router bgp .*",
+            "-",
+        ])
+        .write_stdin("hostname edge1\nrouter bgp 65010\n")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("! cfgcut matches for edge1\nrouter bgp 65010\n"));
+    let record = stdout
+        .lines()
+        .find(|line| line.starts_with('{'))
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+        .unwrap();
+    assert_eq!(record["source"], "edge1");
+    assert_eq!(record["line"], 2);
+
+    let tmp = tempdir().unwrap();
+    let a = tmp.path().join("a.cfg");
+    let b = tmp.path().join("b.cfg");
+    fs::write(&a, "hostname a\n").unwrap();
+    fs::write(&b, "hostname b\n").unwrap();
+    cfgcut_cmd()
+        .args(["-m", "hostname .*"])
+        .arg(&a)
+        .arg("-")
+        .arg(&b)
+        .write_stdin("hostname edge1\n")
+        .assert()
+        .success()
+        .stdout(
+            "! cfgcut matches for a.cfg\nhostname a\n\
+             ! cfgcut matches for stdin\nhostname edge1\n\
+             ! cfgcut matches for b.cfg\nhostname b\n",
+        );
+}
+
+#[test]
+fn compressed_files_and_archive_members_are_read_directly() {
+    use std::io::Write;
+
+    let tmp = tempdir().unwrap();
+    let dir = tmp.path();
+    let config = |host: &str, asn: u32| format!("hostname {host}\nrouter bgp {asn}\n");
+
+    let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    gzip.write_all(config("edge1", 65001).as_bytes()).unwrap();
+    fs::write(dir.join("edge1.cfg.gz"), gzip.finish().unwrap()).unwrap();
+
+    let mut archive = tar::Builder::new(Vec::new());
+    // Members are read in path order, not archive order.
+    for (name, contents) in [
+        (
+            "devices/edge3.cfg.zst",
+            zstd::encode_all(config("edge3", 65003).as_bytes(), 0).unwrap(),
+        ),
+        ("devices/edge2.cfg", config("edge2", 65002).into_bytes()),
+    ] {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        archive
+            .append_data(&mut header, name, contents.as_slice())
+            .unwrap();
+    }
+    let archive = archive.into_inner().unwrap();
+    fs::write(
+        dir.join("weekly.tar.zst"),
+        zstd::encode_all(archive.as_slice(), 0).unwrap(),
+    )
+    .unwrap();
+
+    let dir_str = dir.to_string_lossy().into_owned();
+    let output = cfgcut_cmd()
+        .args(["--tokens", "-m", "router bgp .*", dir_str.as_str()])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let parallel = cfgcut_cmd()
+        .args([
+            "--tokens",
+            "-j",
+            "4",
+            "-m",
+            "router bgp .*",
+            dir_str.as_str(),
+        ])
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8(parallel.stdout).unwrap(), stdout);
+
+    let headings = stdout
+        .lines()
+        .filter(|line| line.starts_with("! cfgcut"))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        headings,
+        vec![
+            "! cfgcut matches for edge1.cfg.gz",
+            "! cfgcut matches for weekly.tar.zst:devices/edge2.cfg",
+            "! cfgcut matches for weekly.tar.zst:devices/edge3.cfg.zst",
+        ]
+    );
+    let sources = stdout
+        .lines()
+        .filter(|line| line.starts_with('{'))
+        .map(|line| {
+            let record: serde_json::Value = serde_json::from_str(line).unwrap();
+            record["source"].as_str().unwrap().to_string()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        sources,
+        vec![
+            "edge1.cfg.gz",
+            "weekly.tar.zst:devices/edge2.cfg",
+            "weekly.tar.zst:devices/edge3.cfg.zst",
+        ]
+    );
+}
+
+#[test]
+fn git_rev_reads_inputs_from_a_past_revision() {
+    let tmp = tempdir().unwrap();
+    let repo = tmp.path();
+    let git = |args: &[&str]| {
+        let status = std::process::Command::new("git")
+            .args([
+                "-c",
+                "user.name=cfgcut",
+                "-c",
+                "user.email=cfgcut@example.net",
+            ])
+            .args(args)
+            .current_dir(repo)
+            .status()
+            .unwrap();
+        assert!(status.success(), "git {args:?}");
+    };
+    let devices = repo.join("devices");
+    fs::create_dir(&devices).unwrap();
+    git(&["init", "-q"]);
+    fs::write(
+        devices.join("edge1.cfg"),
+        "hostname edge1\nrouter bgp 65001\n",
+    )
+    .unwrap();
+    git(&["add", "."]);
+    git(&["commit", "-q", "-m", "first"]);
+    fs::write(
+        devices.join("edge1.cfg"),
+        "hostname edge1\nrouter bgp 65009\n",
+    )
+    .unwrap();
+    fs::write(
+        devices.join("edge2.cfg"),
+        "hostname edge2\nrouter bgp 65002\n",
+    )
+    .unwrap();
+    git(&["add", "."]);
+    git(&["commit", "-q", "-m", "second"]);
+    git(&["rm", "-q", "devices/edge1.cfg"]);
+    git(&["commit", "-q", "-m", "third"]);
+
+    let file = devices.join("edge1.cfg");
+    let file_str = file.to_string_lossy().into_owned();
+    let mut first = cfgcut_cmd();
+    first
+        .args([
+            "--git-rev",
+            "HEAD~2",
+            "-m",
+            "router bgp .*",
+            file_str.as_str(),
+        ])
+        .assert()
+        .success()
+        .stdout("! cfgcut matches for edge1.cfg@HEAD~2\nrouter bgp 65001\n");
+
+    let dir_str = devices.to_string_lossy().into_owned();
+    let mut second = cfgcut_cmd();
+    second
+        .args([
+            "--git-rev",
+            "HEAD~1",
+            "-m",
+            "router bgp .*",
+            dir_str.as_str(),
+        ])
+        .assert()
+        .success()
+        .stdout(
+            "! cfgcut matches for edge1.cfg@HEAD~1\nrouter bgp 65009\n\
+             ! cfgcut matches for edge2.cfg@HEAD~1\nrouter bgp 65002\n",
+        );
+
+    let pattern = format!("{dir_str}/*1.cfg");
+    let mut glob = cfgcut_cmd();
+    glob.args([
+        "--git-rev",
+        "HEAD~1",
+        "-m",
+        "router bgp .*",
+        pattern.as_str(),
+    ])
+    .assert()
+    .success()
+    .stdout("! cfgcut matches for edge1.cfg@HEAD~1\nrouter bgp 65009\n");
+
+    let mut deleted = cfgcut_cmd();
+    deleted
+        .args([
+            "--git-rev",
+            "HEAD",
+            "-m",
+            "router bgp .*",
+            file_str.as_str(),
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "at revision 'HEAD': no such path at this revision",
+        ));
+}
+
+#[test]
+fn git_trees_honour_ignore_files_and_relative_filters_like_the_disk() {
+    let tmp = tempdir().unwrap();
+    let repo = tmp.path();
+    let git = |args: &[&str]| {
+        let status = std::process::Command::new("git")
+            .args([
+                "-c",
+                "user.name=cfgcut",
+                "-c",
+                "user.email=cfgcut@example.net",
+            ])
+            .args(args)
+            .current_dir(repo)
+            .status()
+            .unwrap();
+        assert!(status.success(), "git {args:?}");
+    };
+    fs::create_dir_all(repo.join("backups/devices/lab")).unwrap();
+    fs::create_dir_all(repo.join("backups/spare")).unwrap();
+    fs::write(repo.join(".cfgcutignore"), "*.bak\n").unwrap();
+    fs::write(repo.join("backups/devices/.cfgcutignore"), "lab/\n").unwrap();
+    for (path, host) in [
+        ("backups/devices/edge1.cfg", "edge1"),
+        ("backups/devices/edge1.cfg.bak", "old"),
+        ("backups/devices/lab/lab1.cfg", "lab1"),
+        ("backups/spare/spare1.cfg", "spare1"),
+    ] {
+        fs::write(repo.join(path), format!("hostname {host}\n")).unwrap();
+    }
+    git(&["init", "-q"]);
+    git(&["add", "-f", "."]);
+    git(&["commit", "-q", "-m", "backups"]);
+
+    let hosts = |args: &[&str], input: &Path| {
+        let output = cfgcut_cmd()
+            .args(args)
+            .args(["-m", "hostname .*"])
+            .arg(input)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout)
+            .unwrap()
+            .lines()
+            .filter(|line| line.starts_with("hostname"))
+            .map(str::to_string)
+            .collect::<Vec<_>>()
+    };
+
+    let devices = repo.join("backups/devices");
+    assert_eq!(hosts(&[], &devices), vec!["hostname edge1"]);
+    assert_eq!(
+        hosts(&["--git-rev", "HEAD"], &devices),
+        vec!["hostname edge1"]
+    );
+    let backups = repo.join("backups");
+    assert_eq!(
+        hosts(&["--git-rev", "HEAD"], &backups),
+        vec!["hostname edge1", "hostname spare1"]
+    );
+
+    let pattern = repo.join("backups/*/*.cfg");
+    let include = ["--include", "devices/*.cfg"];
+    assert_eq!(hosts(&include, &pattern), vec!["hostname edge1"]);
+    let from_git = ["--git-rev", "HEAD", "--include", "devices/*.cfg"];
+    assert_eq!(hosts(&from_git, &pattern), vec!["hostname edge1"]);
+}
+
+#[test]
+fn directory_walks_honour_filters_ignore_files_and_skip_binaries() {
+    let tmp = tempdir().unwrap();
+    let dir = tmp.path();
+    let config = |host: &str| format!("hostname {host}\n");
+    fs::create_dir_all(dir.join(".git/objects")).unwrap();
+    fs::create_dir_all(dir.join("devices/lab")).unwrap();
+    fs::write(dir.join(".git/HEAD"), "ref: refs/heads/main\n").unwrap();
+    fs::write(dir.join(".cfgcutignore"), "lab/\n*.bak\n").unwrap();
+    fs::write(dir.join("README.md"), "# backups\n").unwrap();
+    fs::write(dir.join("devices/edge1.cfg"), config("edge1")).unwrap();
+    fs::write(dir.join("devices/edge1.cfg.bak"), config("old")).unwrap();
+    fs::write(dir.join("devices/.edge1.cfg.swp"), config("swap")).unwrap();
+    fs::write(dir.join("devices/edge2.cfg"), config("edge2")).unwrap();
+    fs::write(dir.join("devices/lab/lab1.cfg"), config("lab1")).unwrap();
+    fs::write(
+        dir.join("devices/core1.cfg"),
+        b"\x00\x01\x02hostname core1\n",
+    )
+    .unwrap();
+
+    let dir_str = dir.to_string_lossy().into_owned();
+    let output = cfgcut_cmd()
+        .args([
+            "--include",
+            "*.cfg",
+            "--exclude",
+            "devices/edge2.*",
+            "-m",
+            "hostname .*",
+            dir_str.as_str(),
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "! cfgcut matches for edge1.cfg\nhostname edge1\n"
+    );
+
+    let core1 = dir.join("devices/core1.cfg");
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        format!(
+            "warning: skipping {}: binary file (use --encoding if it is text)\n",
+            core1.display()
+        )
+    );
+}
+
+#[test]
+fn invalid_filter_glob_is_rejected() {
+    let path = fixture_path("cisco_ios/sample.conf");
+    let path_str = path.to_string_lossy().into_owned();
+    let mut cmd = cfgcut_cmd();
+    cmd.args(["--include", "[", "-m", ".*", path_str.as_str()])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid filter pattern '['"));
+}
+
+#[test]
+fn utf16_and_latin1_inputs_are_decoded_and_crlf_is_kept() {
+    let tmp = tempdir().unwrap();
+    let dir = tmp.path();
+    let mut utf16 = vec![0xff, 0xfe];
+    utf16.extend(
+        "hostname edge1\r\ninterface Gi1\r\n description uplink\r\n"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes),
+    );
+    let windows = dir.join("edge1.cfg");
+    fs::write(&windows, utf16).unwrap();
+    let archive = dir.join("edge2.cfg");
+    fs::write(
+        &archive,
+        b"hostname edge2\ninterface Gi1\n description caf\xe9\n",
+    )
+    .unwrap();
+
+    let dir_str = dir.to_string_lossy().into_owned();
+    let output = cfgcut_cmd()
+        .args(["-m", "interface .*|>>|", dir_str.as_str()])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "! cfgcut matches for edge1.cfg\r\ninterface Gi1\r\n description uplink\r\n\
+         ! cfgcut matches for edge2.cfg\ninterface Gi1\n description caf\u{e9}\n"
+    );
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        format!(
+            "warning: {}: decoded as UTF-16LE from its byte order mark\n\
+             warning: {}: not valid UTF-8, decoded as windows-1252\n",
+            windows.display(),
+            archive.display()
+        )
+    );
+
+    let mut forced = cfgcut_cmd();
+    forced
+        .args([
+            "--encoding",
+            "latin1",
+            "-m",
+            "hostname .*",
+            archive.to_str().unwrap(),
+        ])
+        .assert()
+        .success()
+        .stderr("");
+
+    let mut unknown = cfgcut_cmd();
+    unknown
+        .args([
+            "--encoding",
+            "klingon",
+            "-m",
+            ".*",
+            archive.to_str().unwrap(),
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown encoding 'klingon'"));
+}
+
+#[test]
+fn parallel_jobs_match_a_serial_run_byte_for_byte() {
+    let tmp = tempdir().unwrap();
+    let dir = tmp.path();
+    for device in 0..24 {
+        // Later devices are larger, so they tend to finish last, and they
+        // reuse earlier addresses so anonymized values depend on input order.
+        let mut config = format!("hostname edge{device}\n");
+        for peer in 0..device * 20 {
+            config.push_str(&format!(
+                "interface Gi0/{peer}\n ip address 10.{}.{}.1 255.255.255.0\n",
+                peer % 7,
+                device % 5
+            ));
+        }
+        config.push_str(&format!(
+            "username ops{device} secret 5 $1$abc{device}\nrouter bgp {}\n neighbor 192.0.2.{device} remote-as 65{device:03}\n",
+            64_500 + device
+        ));
+        if device % 6 == 0 {
+            config = config.replace('\n', "\r\n");
+        }
+        fs::write(dir.join(format!("edge{device:02}.cfg")), config).unwrap();
+    }
+    fs::write(dir.join("empty.cfg"), "hostname lonely\n").unwrap();
+
+    let dir_str = dir.to_string_lossy().into_owned();
+    let run = |jobs: &str| {
+        cfgcut_cmd()
+            .args([
+                "-j",
+                jobs,
+                "-a",
+                "--tokens",
+                "-m",
+                "interface .*|>>|",
+                "-m",
+                "username .*",
+                "-m",
+                "router bgp .*|>>|",
+                dir_str.as_str(),
+            ])
+            .output()
+            .unwrap()
+    };
+
+    let serial = run("1");
+    assert!(serial.status.success());
+    assert!(!serial.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&serial.stderr).contains("no matches found"));
+    for jobs in ["4", "0"] {
+        let parallel = run(jobs);
+        assert!(parallel.status.success());
+        assert_eq!(parallel.stdout, serial.stdout);
+        assert_eq!(parallel.stderr, serial.stderr);
+    }
+}
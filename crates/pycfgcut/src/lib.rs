@@ -1,8 +1,8 @@
 use std::path::PathBuf;
 
 use cfgcut::{
-    Anonymization, CfgcutError, CommentHandling, OutputMode, RenderOrder, RunRequest,
-    TokenDestination, TokenRecord, run,
+    Anonymization, CaptureHandling, CfgcutError, CommentHandling, DialectKind, EmitSyntax,
+    InputSource, OutputMode, RenderOrder, RunRequest, TokenDestination, TokenRecord, run,
 };
 use pyo3::Bound;
 use pyo3::exceptions::PyRuntimeError;
@@ -10,7 +10,7 @@ use pyo3::prelude::*;
 use pyo3::types::{PyDict, PyModule};
 
 #[pyfunction]
-#[pyo3(signature = (matches, inputs, with_comments = false, sort_by_path = false, quiet = false, anonymize = false, tokens = false, tokens_out = None, within = None, requirements = None))]
+#[pyo3(signature = (matches, inputs = None, with_comments = false, sort_by_path = false, quiet = false, anonymize = false, tokens = false, tokens_out = None, within = None, requirements = None, dialect = None, emit = None, device_delimiter = None, raw = false, texts = None, git_rev = None, include = None, exclude = None, encoding = None, jobs = 1))]
 #[expect(
     clippy::too_many_arguments,
     reason = "Python binding mirrors the CLI surface without breaking parameters"
@@ -18,7 +18,7 @@ use pyo3::types::{PyDict, PyModule};
 fn run_cfg(
     py: Python<'_>,
     matches: Vec<String>,
-    inputs: Vec<String>,
+    inputs: Option<Vec<String>>,
     with_comments: bool,
     sort_by_path: bool,
     quiet: bool,
@@ -27,19 +27,50 @@ fn run_cfg(
     tokens_out: Option<String>,
     within: Option<String>,
     requirements: Option<Vec<String>>,
+    dialect: Option<String>,
+    emit: Option<String>,
+    device_delimiter: Option<String>,
+    raw: bool,
+    texts: Option<Bound<'_, PyDict>>,
+    git_rev: Option<String>,
+    include: Option<Vec<String>>,
+    exclude: Option<Vec<String>>,
+    encoding: Option<String>,
+    jobs: usize,
 ) -> PyResult<Py<PyAny>> {
     if matches.is_empty() {
         return Err(PyRuntimeError::new_err(
             "at least one match expression is required",
         ));
     }
-    if inputs.is_empty() {
+    let mut sources = inputs
+        .unwrap_or_default()
+        .into_iter()
+        .map(|input| InputSource::Path(PathBuf::from(input)))
+        .collect::<Vec<_>>();
+    if let Some(texts) = texts {
+        for (label, text) in texts.iter() {
+            sources.push(InputSource::text(
+                label.extract::<String>()?,
+                text.extract::<String>()?,
+            ));
+        }
+    }
+    if sources.is_empty() {
         return Err(PyRuntimeError::new_err(
-            "at least one input path is required",
+            "at least one input path or text is required",
         ));
     }
 
-    let paths = inputs.into_iter().map(PathBuf::from).collect::<Vec<_>>();
+    let dialect = dialect
+        .map(|name| name.parse::<DialectKind>())
+        .transpose()
+        .map_err(|err| PyRuntimeError::new_err(err.to_string()))?;
+    let emit = emit
+        .map(|name| name.parse::<EmitSyntax>())
+        .transpose()
+        .map_err(|err| PyRuntimeError::new_err(err.to_string()))?;
+
     let token_output = tokens_out
         .map(PathBuf::from)
         .map(TokenDestination::File)
@@ -69,8 +100,21 @@ fn run_cfg(
         } else {
             Anonymization::Disabled
         })
-        .inputs(paths)
+        .sources(sources)
         .token_output(token_output)
+        .dialect(dialect)
+        .emit_syntax(emit.unwrap_or_default())
+        .device_delimiter(device_delimiter)
+        .capture_handling(if raw {
+            CaptureHandling::Raw
+        } else {
+            CaptureHandling::Clean
+        })
+        .git_revision(git_rev)
+        .include(include.unwrap_or_default())
+        .exclude(exclude.unwrap_or_default())
+        .encoding(encoding)
+        .jobs(jobs)
         .build();
 
     match run(&request) {
@@ -85,6 +129,7 @@ fn run_cfg(
             dict.set_item("stdout", result.stdout)?;
             dict.set_item("matched", result.matched)?;
             dict.set_item("tokens", tokens_to_py(py, &result.tokens)?)?;
+            dict.set_item("warnings", result.warnings)?;
             Ok(dict.into())
         }
         Err(err) => Err(PyRuntimeError::new_err(err.to_string())),
@@ -97,6 +142,7 @@ fn tokens_to_py(py: Python<'_>, tokens: &[TokenRecord]) -> PyResult<Vec<Py<PyAny
         .map(|record| {
             let dict = PyDict::new(py);
             dict.set_item("dialect", format!("{:?}", record.dialect))?;
+            dict.set_item("source", record.source.clone())?;
             dict.set_item("path", record.path.clone())?;
             dict.set_item("kind", record.kind.as_str())?;
             dict.set_item("original", record.original.clone())?;
@@ -102,6 +102,53 @@ def test_tokens_flag_followed(anonymize: bool):
         assert all(token["anonymized"] is None for token in result["tokens"])
 
 
+def test_dialect_override(tmp_path: Path):
+    config = tmp_path / "edge.set"
+    config.write_text(
+        "! exported by backup tool\nset system host-name edge1\n",
+        encoding="utf-8",
+    )
+
+    result = run_cfg(
+        ["system||host-name edge1"], [str(config)], dialect="juniper-junos-set"
+    )
+
+    assert result["matched"] is True
+    assert "set system host-name edge1" in result["stdout"]
+
+    with pytest.raises(RuntimeError):
+        run_cfg(["system"], [str(config)], dialect="bogus")
+
+
+def test_texts_are_scanned_under_their_labels():
+    result = run_cfg(
+        ["router bgp .*"],
+        texts={"edge1": "hostname edge1\nrouter bgp 65010\n"},
+        tokens=True,
+    )
+
+    assert result["stdout"].startswith("! cfgcut matches for edge1\n")
+    assert {token["source"] for token in result["tokens"]} == {"edge1"}
+
+
+def test_warnings_are_returned():
+    result = run_cfg(
+        ["interface .*"],
+        texts={
+            "edge1": "feature bgp\ninterface Ethernet1/1\n  description x\n"
+            "management api http-commands\n no shutdown\n",
+            "edge2": "hostname edge2\n",
+        },
+    )
+
+    assert result["matched"] is True
+    assert result["warnings"] == [
+        "warning: edge1: ambiguous dialect detection, chose arista-eos (score 25)"
+        " over cisco-nxos (score 20); use --dialect to override",
+        "warning: no matches found in edge2",
+    ]
+
+
 def test_invalid_inputs_raise():
     fixture = _fixture_path("juniper_junos/sample.conf")
 